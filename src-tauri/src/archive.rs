@@ -0,0 +1,84 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::Serialize;
+use tauri::State;
+
+use crate::error::{classify_ytdlp_error, AppError};
+use crate::get_ytdlp_path;
+use crate::settings::SettingsStore;
+
+// yt-dlp download archive kept at the root of each library folder
+const ARCHIVE_FILE: &str = ".grably-archive.txt";
+
+pub fn archive_path(library_dir: &Path) -> PathBuf {
+    library_dir.join(ARCHIVE_FILE)
+}
+
+// Arguments that make yt-dlp record finished media in the library's
+// archive and skip anything already recorded there
+pub fn archive_args(library_dir: &Path) -> Vec<String> {
+    vec![
+        "--download-archive".to_string(),
+        archive_path(library_dir).to_string_lossy().to_string(),
+    ]
+}
+
+// yt-dlp archive lines are "<extractor key in lowercase> <media id>"
+pub fn archive_key(extractor_key: &str, id: &str) -> String {
+    format!("{} {}", extractor_key.to_lowercase(), id)
+}
+
+pub fn is_archived(library_dir: &Path, key: &str) -> bool {
+    fs::read_to_string(archive_path(library_dir))
+        .map(|content| content.lines().any(|line| line.trim() == key))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveCheck {
+    downloaded: bool,
+    // "<extractor> <id>" as stored in the archive
+    archive_key: String,
+    archive_path: String,
+}
+
+// Check whether a URL is already in a library's download archive, so the
+// UI can badge it before the user starts a download
+#[tauri::command]
+pub async fn check_already_downloaded(
+    settings: State<'_, SettingsStore>,
+    url: String,
+    output_dir: Option<String>,
+) -> Result<ArchiveCheck, AppError> {
+    let library_dir = output_dir.map(PathBuf::from)
+        .unwrap_or_else(|| settings.get().output_dir());
+
+    let output = Command::new(get_ytdlp_path())
+        .args([
+            "--skip-download",
+            "--no-playlist",
+            "--no-warnings",
+            "--print", "%(extractor_key)s %(id)s",
+            &url,
+        ])
+        .output()
+        .map_err(|e| AppError::spawn("yt-dlp", e))?;
+
+    if !output.status.success() {
+        return Err(classify_ytdlp_error(&String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (extractor_key, id) = stdout.lines()
+        .next()
+        .and_then(|line| line.trim().split_once(' '))
+        .ok_or_else(|| AppError::invalid(format!("Could not identify media at {}", url)))?;
+    let key = archive_key(extractor_key, id);
+
+    Ok(ArchiveCheck {
+        downloaded: is_archived(&library_dir, &key),
+        archive_key: key,
+        archive_path: archive_path(&library_dir).to_string_lossy().to_string(),
+    })
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, ErrorCode};
+use crate::feeds::safe_file_name;
+use crate::get_ffmpeg_path;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Chapter {
+    // 1-based position in the video
+    pub index: usize,
+    pub title: String,
+    pub start_time: f64,
+    // Up to the end of the media when missing
+    pub end_time: Option<f64>,
+}
+
+// Chapters from the "chapters" array of yt-dlp's -j output
+pub fn parse_chapters(value: &serde_json::Value) -> Vec<Chapter> {
+    value.as_array()
+        .map(|chapters| {
+            chapters.iter()
+                .enumerate()
+                .filter_map(|(i, chapter)| {
+                    Some(Chapter {
+                        index: i + 1,
+                        title: chapter["title"].as_str()
+                            .map(|title| title.trim().to_string())
+                            .filter(|title| !title.is_empty())
+                            .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+                        start_time: chapter["start_time"].as_f64()?,
+                        end_time: chapter["end_time"].as_f64(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Split a download into one file per chapter
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ChapterSplit {
+    // 1-based chapter indices to keep, all chapters when empty
+    pub chapters: Vec<usize>,
+    // File name without extension, overrides the chapter_template setting
+    pub template: Option<String>,
+    // Keep the unsplit file next to the chapters
+    pub keep_full: bool,
+}
+
+// Fill in {index}, {title} and {video} of a chapter file name template
+fn chapter_file_name(template: &str, chapter: &Chapter, video: &str, count: usize) -> String {
+    let width = count.to_string().len().max(2);
+    let name = template
+        .replace("{index}", &format!("{:0width$}", chapter.index, width = width))
+        .replace("{title}", &chapter.title)
+        .replace("{video}", video);
+    safe_file_name(&name)
+}
+
+impl ChapterSplit {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.chapters.contains(&0) {
+            return Err(AppError::invalid("Chapters start at 1"));
+        }
+        if self.template.as_deref().is_some_and(|template| template.trim().is_empty()) {
+            return Err(AppError::invalid("Chapter file name template is empty"));
+        }
+        Ok(())
+    }
+
+    // Cut a downloaded file into its chapters with ffmpeg. The files go into
+    // a folder named after the video, next to it. Returns the new paths.
+    pub fn split_file(
+        &self,
+        path: &Path,
+        chapters: &[Chapter],
+        default_template: &str,
+    ) -> Result<Vec<String>, AppError> {
+        let template = self.template.as_deref().unwrap_or(default_template);
+        let video = path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let ext = path.extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_else(|| "mp4".to_string());
+        let dir = path.with_file_name(safe_file_name(&video));
+        fs::create_dir_all(&dir)?;
+
+        let mut outputs = Vec::new();
+        for chapter in chapters {
+            if !self.chapters.is_empty() && !self.chapters.contains(&chapter.index) {
+                continue;
+            }
+            let name = chapter_file_name(template, chapter, &video, chapters.len());
+            let output = dir.join(format!("{}.{}", name, ext));
+            let mut command = Command::new(get_ffmpeg_path());
+            command.arg("-y")
+                .args(["-ss", &chapter.start_time.to_string()]);
+            if let Some(end) = chapter.end_time {
+                command.args(["-to", &end.to_string()]);
+            }
+            command.arg("-i").arg(path)
+                // Chapter markers sit on keyframes often enough, no re-encode
+                .args(["-c", "copy", "-map", "0", "-map_chapters", "-1", "-avoid_negative_ts", "make_zero"])
+                .args(["-metadata", &format!("title={}", chapter.title)])
+                .arg(&output);
+
+            let result = command.output().map_err(|e| AppError::spawn("ffmpeg", e))?;
+            if !result.status.success() {
+                let _ = fs::remove_file(&output);
+                return Err(AppError::new(ErrorCode::FfmpegFailed, "Failed to split the chapters")
+                    .with_stderr(&String::from_utf8_lossy(&result.stderr)));
+            }
+            outputs.push(output.to_string_lossy().to_string());
+        }
+
+        if outputs.is_empty() {
+            return Err(AppError::invalid("None of the selected chapters exist in this video"));
+        }
+        if !self.keep_full {
+            let _ = fs::remove_file(path);
+        }
+        Ok(outputs)
+    }
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, ErrorCode};
+use crate::get_ffmpeg_path;
+
+// Added to the output template so each downloaded section gets its own file
+const SECTION_SUFFIX: &str = " [%(section_start)s-%(section_end)s]";
+
+// One part of the media to keep, timestamps as "90", "1:30" or "01:02:03.5"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimeRange {
+    pub start: String,
+    // Up to the end of the media when missing
+    pub end: Option<String>,
+}
+
+// Download only parts of a video
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ClipOptions {
+    // Shorthand for a single range
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub ranges: Vec<TimeRange>,
+    // Re-encode around the cuts for frame-accurate boundaries, otherwise
+    // cut on the nearest keyframes without re-encoding
+    pub accurate: bool,
+    // Download everything and trim with ffmpeg afterwards. Used for
+    // extractors that can't download sections.
+    pub trim_after_download: bool,
+}
+
+// Seconds from "SS", "MM:SS" or "HH:MM:SS" with optional fractions
+fn parse_timestamp(text: &str) -> Result<f64, AppError> {
+    let mut seconds = 0.0;
+    for part in text.trim().split(':') {
+        let value: f64 = part.trim().parse()
+            .map_err(|_| AppError::invalid(format!("Invalid timestamp: {}", text)))?;
+        if value < 0.0 {
+            return Err(AppError::invalid(format!("Invalid timestamp: {}", text)));
+        }
+        seconds = seconds * 60.0 + value;
+    }
+    Ok(seconds)
+}
+
+// Label used in file names, e.g. "90-150" or "90-end"
+fn range_label(start: f64, end: Option<f64>) -> String {
+    match end {
+        Some(end) => format!("{}-{}", start, end),
+        None => format!("{}-end", start),
+    }
+}
+
+impl ClipOptions {
+    // All ranges in seconds, validated
+    pub fn ranges(&self) -> Result<Vec<(f64, Option<f64>)>, AppError> {
+        let shorthand = (self.start.is_some() || self.end.is_some()).then(|| TimeRange {
+            start: self.start.clone().unwrap_or_else(|| "0".to_string()),
+            end: self.end.clone(),
+        });
+        let mut ranges = Vec::new();
+        for range in shorthand.iter().chain(&self.ranges) {
+            let start = parse_timestamp(&range.start)?;
+            let end = range.end.as_deref().map(parse_timestamp).transpose()?;
+            if end.is_some_and(|end| end <= start) {
+                return Err(AppError::invalid(format!(
+                    "Clip end must be after its start: {}", range_label(start, end)
+                )));
+            }
+            ranges.push((start, end));
+        }
+        if ranges.is_empty() {
+            return Err(AppError::invalid("No clip range given"));
+        }
+        Ok(ranges)
+    }
+
+    // Add the clip to a yt-dlp argument list that ends with the URL. Each
+    // range becomes its own file, so the output template gets the range.
+    pub fn apply(&self, args: &mut Vec<String>) -> Result<(), AppError> {
+        let ranges = self.ranges()?;
+        if self.trim_after_download {
+            return Ok(());
+        }
+
+        if let Some(at) = args.iter().position(|arg| arg == "-o") {
+            if let Some(template) = args.get_mut(at + 1) {
+                if let Some((stem, rest)) = template.rsplit_once(".%(ext)s") {
+                    *template = format!("{}{}.%(ext)s{}", stem, SECTION_SUFFIX, rest);
+                }
+            }
+        }
+
+        let mut clip_args = Vec::new();
+        for (start, end) in ranges {
+            let end = end.map(|end| end.to_string()).unwrap_or_else(|| "inf".to_string());
+            clip_args.push("--download-sections".to_string());
+            clip_args.push(format!("*{}-{}", start, end));
+        }
+        if self.accurate {
+            clip_args.push("--force-keyframes-at-cuts".to_string());
+        }
+        let url_at = args.len().saturating_sub(1);
+        args.splice(url_at..url_at, clip_args);
+        Ok(())
+    }
+
+    // Switch a job to downloading in full and trimming afterwards
+    pub fn fall_back_to_trim(&mut self, args: &mut Vec<String>) {
+        self.trim_after_download = true;
+        let mut kept = Vec::new();
+        let mut iter = std::mem::take(args).into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--download-sections" => {
+                    iter.next();
+                }
+                "--force-keyframes-at-cuts" => {}
+                _ => kept.push(arg.replace(SECTION_SUFFIX, "")),
+            }
+        }
+        *args = kept;
+    }
+}
+
+// Whether yt-dlp's stderr says the sections themselves failed: the
+// extractor or format can't be cut while downloading, or ffmpeg (which
+// downloads sections) couldn't cut at keyframes. Other errors aren't
+// fixed by a full download.
+pub fn sections_unsupported(stderr: &str) -> bool {
+    stderr.lines()
+        .filter(|line| line.contains("ERROR") || line.contains("WARNING"))
+        .map(|line| line.to_lowercase())
+        .any(|line| {
+            (line.contains("section") && (line.contains("not support") || line.contains("unsupported") || line.contains("cannot")))
+                || line.contains("keyframes")
+                || line.contains("ffmpeg exited with code")
+        })
+}
+
+// Cut the ranges out of a downloaded file with ffmpeg, one file per range.
+// The full download is removed afterwards. Returns the new paths.
+pub fn trim_file(path: &Path, options: &ClipOptions) -> Result<Vec<String>, AppError> {
+    let ranges = options.ranges()?;
+    let stem = path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path.extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_else(|| "mp4".to_string());
+
+    let mut outputs = Vec::new();
+    for (start, end) in ranges {
+        let output = path.with_file_name(format!("{} [{}].{}", stem, range_label(start, end), ext));
+        let mut command = Command::new(get_ffmpeg_path());
+        command.arg("-y")
+            .args(["-ss", &start.to_string()]);
+        if let Some(end) = end {
+            command.args(["-to", &end.to_string()]);
+        }
+        command.arg("-i").arg(path);
+        if !options.accurate {
+            // Keyframe cut, no re-encode
+            command.args(["-c", "copy", "-avoid_negative_ts", "make_zero"]);
+        }
+        command.arg(&output);
+
+        let result = command.output().map_err(|e| AppError::spawn("ffmpeg", e))?;
+        if !result.status.success() {
+            let _ = fs::remove_file(&output);
+            return Err(AppError::new(ErrorCode::FfmpegFailed, "Failed to cut the clip")
+                .with_stderr(&String::from_utf8_lossy(&result.stderr)));
+        }
+        outputs.push(output.to_string_lossy().to_string());
+    }
+    let _ = fs::remove_file(path);
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_unsupported_sections() {
+        let cases = [
+            ("ERROR: [generic] This format does not support section downloading", true),
+            ("WARNING: Cannot download sections of live streams", true),
+            ("ERROR: ffmpeg exited with code 1", true),
+            ("ERROR: Postprocessing: Error forcing keyframes at cuts", true),
+            ("ERROR: unable to download video data: HTTP Error 403: Forbidden", false),
+            ("ERROR: [youtube] abc: Private video. Sign in if you've been granted access", false),
+            ("ERROR: Postprocessing: Conversion failed!", false),
+            ("[download] Destination: clip [90-150].mp4", false),
+        ];
+        for (stderr, expected) in cases {
+            assert_eq!(sections_unsupported(stderr), expected, "{}", stderr);
+        }
+    }
+}
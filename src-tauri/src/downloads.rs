@@ -0,0 +1,1129 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStderr, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{get_ytdlp_path, parse_progress, universal_status, youtube_status};
+use crate::chapters::{self, ChapterSplit};
+use crate::clip::{self, ClipOptions};
+use crate::error::{classify_ytdlp_error, AppError, ErrorCode};
+use crate::settings::SettingsStore;
+use crate::{history, queue};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadState {
+    Queued,
+    Starting,
+    Downloading,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl DownloadState {
+    pub fn is_terminal(self) -> bool {
+        matches!(self, DownloadState::Completed | DownloadState::Failed | DownloadState::Cancelled)
+    }
+
+    // States that hold one of the max_concurrent slots
+    pub fn is_running(self) -> bool {
+        matches!(self, DownloadState::Starting | DownloadState::Downloading)
+    }
+}
+
+// Which command created the download, decides how log lines are reported
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadKind {
+    Youtube,
+    Universal,
+}
+
+impl DownloadKind {
+    fn status_for(self, line: &str) -> Option<&'static str> {
+        match self {
+            DownloadKind::Youtube => youtube_status(line),
+            DownloadKind::Universal => universal_status(line),
+        }
+    }
+}
+
+// Snapshot of a download as reported to the frontend
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DownloadInfo {
+    pub id: String,
+    pub url: String,
+    pub filename: String,
+    pub output_dir: String,
+    pub kind: DownloadKind,
+    pub state: DownloadState,
+    pub percent: f32,
+    // Higher priority jobs leave the queue first, equal priorities are FIFO
+    pub priority: i32,
+    pub pid: Option<u32>,
+    pub started_at: u64,
+    pub error: Option<AppError>,
+    // Files yt-dlp announced via "[download] Destination:" lines
+    pub destinations: Vec<String>,
+    // Final file(s) after merging and post-processing, set on completion
+    pub output_paths: Vec<String>,
+    pub filesize: Option<u64>,
+    // Items yt-dlp skipped because the download archive already had them
+    pub skipped: u32,
+    // Items yt-dlp turned down because of --match-filter or --dateafter
+    #[serde(default)]
+    pub filtered: u32,
+    // Current entry when downloading a playlist, percent is then the
+    // progress of the whole playlist
+    #[serde(default)]
+    pub playlist: Option<PlaylistProgress>,
+}
+
+// Position within a playlist download
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlaylistProgress {
+    // 1-based, as yt-dlp reports it
+    pub index: usize,
+    pub count: usize,
+    pub title: Option<String>,
+    pub path: Option<String>,
+    // Progress of the current item only
+    pub percent: f32,
+}
+
+impl PlaylistProgress {
+    fn new(index: usize, count: usize) -> Self {
+        PlaylistProgress {
+            index,
+            count,
+            title: None,
+            path: None,
+            percent: 0.0,
+        }
+    }
+
+    // Percent of the whole playlist, counting earlier items as done
+    fn aggregate_percent(&self) -> f32 {
+        if self.count == 0 {
+            return self.percent;
+        }
+        let done = self.index.saturating_sub(1) as f32 + self.percent / 100.0;
+        (done / self.count as f32 * 100.0).min(100.0)
+    }
+}
+
+impl DownloadInfo {
+    pub fn new(id: &str, url: &str, filename: &str, output_dir: &Path, kind: DownloadKind) -> Self {
+        DownloadInfo {
+            id: id.to_string(),
+            url: url.to_string(),
+            filename: filename.to_string(),
+            output_dir: output_dir.to_string_lossy().to_string(),
+            kind,
+            state: DownloadState::Queued,
+            percent: 0.0,
+            priority: 0,
+            pid: None,
+            started_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            error: None,
+            destinations: Vec::new(),
+            output_paths: Vec::new(),
+            filesize: None,
+            skipped: 0,
+            filtered: 0,
+            playlist: None,
+        }
+    }
+}
+
+// How a download should be paused
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseMode {
+    // Suspend the process in place (SIGSTOP), only available on unix
+    Suspend,
+    // Stop the process but keep the .part file so it can be continued
+    Stop,
+}
+
+// Everything needed to (re)launch yt-dlp for a download
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DownloadJob {
+    pub info: DownloadInfo,
+    pub args: Vec<String>,
+    // args as the job was queued, before resuming or the clip fallback
+    // rewrote them. History re-runs start from these.
+    #[serde(default)]
+    pub original_args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    // Set once yt-dlp has run for this job, relaunches then continue .part files
+    pub resumable: bool,
+    // Only parts of the media are wanted
+    #[serde(default)]
+    pub clip: Option<ClipOptions>,
+    // Cut each finished file into its chapters
+    #[serde(default)]
+    pub chapter_split: Option<ChapterSplit>,
+}
+
+impl DownloadJob {
+    pub fn new(info: DownloadInfo, args: Vec<String>, cwd: Option<PathBuf>) -> Self {
+        DownloadJob {
+            info,
+            original_args: args.clone(),
+            args,
+            cwd,
+            resumable: false,
+            clip: None,
+            chapter_split: None,
+        }
+    }
+}
+
+struct DownloadEntry {
+    job: DownloadJob,
+    child: Option<Child>,
+    suspended: bool,
+    // Insertion order, keeps the queue FIFO within a priority
+    seq: u64,
+}
+
+// Registry of every download in this session, keyed by download_id.
+// Queued entries double as the download queue.
+pub struct DownloadRegistry {
+    entries: Mutex<HashMap<String, DownloadEntry>>,
+    next_seq: Mutex<u64>,
+    max_concurrent: Mutex<usize>,
+}
+
+impl Default for DownloadRegistry {
+    fn default() -> Self {
+        DownloadRegistry {
+            entries: Mutex::new(HashMap::new()),
+            next_seq: Mutex::new(0),
+            max_concurrent: Mutex::new(queue::DEFAULT_MAX_CONCURRENT),
+        }
+    }
+}
+
+impl DownloadRegistry {
+    pub fn insert(&self, job: DownloadJob) {
+        let seq = {
+            let mut next_seq = self.next_seq.lock().unwrap();
+            *next_seq += 1;
+            *next_seq
+        };
+        let id = job.info.id.clone();
+        self.entries.lock().unwrap().insert(id, DownloadEntry {
+            job,
+            child: None,
+            suspended: false,
+            seq,
+        });
+    }
+
+    fn attach_child(&self, id: &str, child: Child) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(id) {
+            entry.job.info.pid = Some(child.id());
+            entry.job.resumable = true;
+            entry.child = Some(child);
+        }
+    }
+
+    pub fn job(&self, id: &str) -> Option<DownloadJob> {
+        self.entries.lock().unwrap().get(id).map(|e| e.job.clone())
+    }
+
+    pub fn get(&self, id: &str) -> Option<DownloadInfo> {
+        self.entries.lock().unwrap().get(id).map(|e| e.job.info.clone())
+    }
+
+    pub fn list(&self) -> Vec<DownloadInfo> {
+        let entries = self.entries.lock().unwrap();
+        let mut list: Vec<&DownloadEntry> = entries.values().collect();
+        list.sort_by_key(|entry| entry.seq);
+        list.into_iter().map(|entry| entry.job.info.clone()).collect()
+    }
+
+    pub fn update<F: FnOnce(&mut DownloadInfo)>(&self, id: &str, f: F) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(id) {
+            f(&mut entry.job.info);
+        }
+    }
+
+    pub fn update_job<F: FnOnce(&mut DownloadJob)>(&self, id: &str, f: F) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(id) {
+            f(&mut entry.job);
+        }
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        *self.max_concurrent.lock().unwrap()
+    }
+
+    pub fn set_max_concurrent(&self, max: usize) {
+        *self.max_concurrent.lock().unwrap() = max.max(1);
+    }
+
+    // Unfinished jobs, in the order they should survive a restart
+    pub fn pending_jobs(&self) -> Vec<DownloadJob> {
+        let entries = self.entries.lock().unwrap();
+        let mut pending: Vec<&DownloadEntry> = entries.values()
+            .filter(|entry| !entry.job.info.state.is_terminal())
+            .collect();
+        pending.sort_by_key(|entry| entry.seq);
+        pending.into_iter().map(|entry| entry.job.clone()).collect()
+    }
+
+    // Queued ids in the order the scheduler will start them
+    pub fn queued_ids(&self) -> Vec<String> {
+        queued_order(&self.entries.lock().unwrap())
+    }
+
+    // Pick the queued jobs that fit in the free slots and mark them Starting
+    pub fn claim_next(&self) -> Vec<String> {
+        let max = self.max_concurrent();
+        let mut entries = self.entries.lock().unwrap();
+        let queued = queued_order(&entries);
+        let running = entries.values()
+            .filter(|entry| entry.job.info.state.is_running())
+            .count();
+        let claimed: Vec<String> = queued.into_iter()
+            .take(max.saturating_sub(running))
+            .collect();
+        for id in &claimed {
+            if let Some(entry) = entries.get_mut(id) {
+                entry.job.info.state = DownloadState::Starting;
+            }
+        }
+        claimed
+    }
+
+    fn take_child(&self, id: &str) -> Option<Child> {
+        self.entries.lock().unwrap().get_mut(id).and_then(|e| e.child.take())
+    }
+
+    // Only hand the child back to the monitor that spawned it, so a monitor
+    // left over from before a resume never reaps the relaunched process
+    fn take_child_if_pid(&self, id: &str, pid: u32) -> Option<Child> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(id)?;
+        if entry.child.as_ref().map(|c| c.id()) != Some(pid) {
+            return None;
+        }
+        entry.child.take()
+    }
+
+    pub fn pause(&self, id: &str, mode: PauseMode) -> Result<DownloadInfo, AppError> {
+        // Only bookkeeping under the lock, signalling and reaping the
+        // process happen once it is released
+        let (info, pid, child) = {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries.get_mut(id)
+                .ok_or_else(|| AppError::not_found(format!("Download not found: {}", id)))?;
+            match entry.job.info.state {
+                DownloadState::Queued => {
+                    // Nothing is running yet, just keep it out of the scheduler
+                    entry.job.info.state = DownloadState::Paused;
+                    return Ok(entry.job.info.clone());
+                }
+                DownloadState::Starting | DownloadState::Downloading => {}
+                DownloadState::Paused => return Err(AppError::invalid(format!("Download already paused: {}", id))),
+                _ => return Err(AppError::invalid(format!("Download already finished: {}", id))),
+            }
+            let pid = entry.job.info.pid.ok_or_else(|| AppError::invalid(format!("Download has no process: {}", id)))?;
+
+            // Suspending is a unix-only feature, everywhere else we stop
+            let mode = if cfg!(unix) { mode } else { PauseMode::Stop };
+            let child = match mode {
+                PauseMode::Suspend => {
+                    entry.suspended = true;
+                    None
+                }
+                PauseMode::Stop => {
+                    entry.suspended = false;
+                    entry.job.info.pid = None;
+                    entry.child.take()
+                }
+            };
+            entry.job.info.state = DownloadState::Paused;
+            (entry.job.info.clone(), pid, child)
+        };
+
+        match child {
+            Some(mut child) => {
+                signal_process_tree(pid, "TERM");
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            None => signal_process_tree(pid, "STOP"),
+        }
+        Ok(info)
+    }
+
+    // Kill the yt-dlp process tree and remove partial files.
+    // Returns the final snapshot so the caller can emit it.
+    pub fn cancel(&self, id: &str) -> Result<DownloadInfo, AppError> {
+        let info = {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries.get_mut(id)
+                .ok_or_else(|| AppError::not_found(format!("Download not found: {}", id)))?;
+            if entry.job.info.state.is_terminal() {
+                return Err(AppError::invalid(format!("Download already finished: {}", id)));
+            }
+            entry.job.info.state = DownloadState::Cancelled;
+            entry.job.info.error = Some(AppError::new(ErrorCode::Cancelled, "Download cancelled"));
+            entry.suspended = false;
+            entry.job.info.clone()
+        };
+
+        if let Some(pid) = info.pid {
+            signal_process_tree(pid, "TERM");
+            // A suspended process only acts on the TERM once continued
+            signal_process_tree(pid, "CONT");
+        }
+        if let Some(mut child) = self.take_child(id) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        for destination in &info.destinations {
+            remove_partial_files(Path::new(destination));
+        }
+
+        Ok(info)
+    }
+}
+
+fn queued_order(entries: &HashMap<String, DownloadEntry>) -> Vec<String> {
+    let mut queued: Vec<&DownloadEntry> = entries.values()
+        .filter(|entry| entry.job.info.state == DownloadState::Queued)
+        .collect();
+    queued.sort_by_key(|entry| (-entry.job.info.priority, entry.seq));
+    queued.into_iter().map(|entry| entry.job.info.id.clone()).collect()
+}
+
+// Spawn yt-dlp in its own process group so the merger/ffmpeg children
+// can be signalled together with it
+fn spawn_in_group(command: &mut Command) -> std::io::Result<Child> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    command.spawn()
+}
+
+// Send a signal (TERM, STOP, CONT) to the whole yt-dlp process group.
+// Windows has no signals, so any request other than CONT kills the tree.
+fn signal_process_tree(pid: u32, signal: &str) {
+    #[cfg(unix)]
+    {
+        let _ = Command::new("kill")
+            .args([&format!("-{}", signal), "--", &format!("-{}", pid)])
+            .output();
+    }
+    #[cfg(windows)]
+    {
+        if signal != "CONT" {
+            let _ = Command::new("taskkill")
+                .args(["/PID", &pid.to_string(), "/T", "/F"])
+                .output();
+        }
+    }
+}
+
+// Put a download in the queue, it starts as soon as a slot is free
+pub fn enqueue_download(
+    app: &AppHandle,
+    info: DownloadInfo,
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+) {
+    enqueue_job(app, DownloadJob::new(info, args, cwd));
+}
+
+pub fn enqueue_job(app: &AppHandle, job: DownloadJob) {
+    let id = job.info.id.clone();
+    let filename = job.info.filename.clone();
+    app.state::<DownloadRegistry>().insert(job);
+    queue::schedule(app);
+
+    if app.state::<DownloadRegistry>().get(&id).map(|info| info.state) == Some(DownloadState::Queued) {
+        app.emit("download-status", serde_json::json!({
+            "id": id,
+            "filename": filename,
+            "status": "Waiting in queue...",
+            "percent": 0.0
+        })).ok();
+    }
+}
+
+// Launch yt-dlp for a registered download
+pub fn launch(app: &AppHandle, download_id: &str) -> Result<(), AppError> {
+    let registry = app.state::<DownloadRegistry>();
+    let (args, cwd, kind) = {
+        let mut entries = registry.entries.lock().unwrap();
+        let entry = entries.get_mut(download_id)
+            .ok_or_else(|| AppError::not_found(format!("Download not found: {}", download_id)))?;
+        if entry.suspended {
+            // A suspended process still has its monitor, continue it
+            // instead of starting another one
+            entry.suspended = false;
+            entry.job.info.state = DownloadState::Downloading;
+            let pid = entry.job.info.pid;
+            drop(entries);
+            if let Some(pid) = pid {
+                signal_process_tree(pid, "CONT");
+            }
+            return Ok(());
+        }
+        if entry.job.resumable {
+            // Drop --force-overwrites so yt-dlp continues the .part file
+            entry.job.args = resume_args(&entry.job.args);
+        }
+        (entry.job.args.clone(), entry.job.cwd.clone(), entry.job.info.kind)
+    };
+
+    // Have yt-dlp record where each file ended up once post-processing
+    // has moved it into place, along with the metadata history needs
+    let mut args = args;
+    let url_at = args.len().saturating_sub(1);
+    args.splice(url_at..url_at, [
+        "--print-to-file".to_string(),
+        "after_move:%(.{filepath,title,uploader,extractor_key,chapters})j".to_string(),
+        finished_items_file(download_id).to_string_lossy().to_string(),
+        // Title of each item as it starts, playlist progress names the
+        // item by it rather than by intermediate file names
+        "--print-to-file".to_string(),
+        "before_dl:%(title)s".to_string(),
+        item_titles_file(download_id).to_string_lossy().to_string(),
+    ]);
+
+    let mut command = Command::new(get_ytdlp_path());
+    command.args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+
+    // Spawn the download process
+    let mut child = spawn_in_group(&mut command)
+        .map_err(|e| AppError::spawn("yt-dlp", e))?;
+    let pid = child.id();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    registry.attach_child(download_id, child);
+
+    // Read all output in background
+    if let Some(stdout) = stdout {
+        monitor_download(app.clone(), download_id.to_string(), pid, stdout, stderr, kind);
+    }
+    Ok(())
+}
+
+// Arguments for continuing a stopped download: never overwrite the partial
+// data we are trying to keep
+fn resume_args(args: &[String]) -> Vec<String> {
+    let mut resumed: Vec<String> = args.iter()
+        .filter(|arg| arg.as_str() != "--force-overwrites")
+        .cloned()
+        .collect();
+    if !resumed.iter().any(|arg| arg == "--continue") {
+        // Keep the URL last
+        let at = resumed.len().saturating_sub(1);
+        resumed.insert(at, "--continue".to_string());
+    }
+    resumed
+}
+
+// Remove yt-dlp leftovers (.part, .ytdl, fragment files) for a destination
+fn remove_partial_files(destination: &Path) {
+    let (Some(dir), Some(name)) = (destination.parent(), destination.file_name()) else {
+        return;
+    };
+    let name = name.to_string_lossy().to_string();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !file_name.starts_with(&name) {
+            continue;
+        }
+        let suffix = &file_name[name.len()..];
+        if suffix.ends_with(".part") || suffix.ends_with(".ytdl") || suffix.contains(".part-Frag") {
+            println!("Removing partial file: {}", file_name);
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+// One media file yt-dlp finished, as printed after it was moved into place
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct FinishedItem {
+    pub filepath: String,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub extractor_key: Option<String>,
+    // yt-dlp's chapter list, null when the media has none
+    #[serde(default)]
+    pub chapters: serde_json::Value,
+}
+
+// File yt-dlp appends a JSON line to for every finished item
+fn finished_items_file(download_id: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("grably_{}.filepath", download_id))
+}
+
+// File yt-dlp appends the title of every item to before downloading it
+fn item_titles_file(download_id: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("grably_{}.titles", download_id))
+}
+
+// Title of the item yt-dlp is downloading now
+fn current_item_title(download_id: &str) -> Option<String> {
+    let content = fs::read_to_string(item_titles_file(download_id)).ok()?;
+    content.lines()
+        .map(|line| line.trim())
+        .rfind(|line| !line.is_empty())
+        .map(|line| line.to_string())
+}
+
+fn take_finished_items(download_id: &str) -> Vec<FinishedItem> {
+    let path = finished_items_file(download_id);
+    let items = fs::read_to_string(&path)
+        .map(|content| {
+            content.lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .map(|line| {
+                    // Fall back to treating the line as a bare path
+                    serde_json::from_str(line).unwrap_or_else(|_| FinishedItem {
+                        filepath: line.to_string(),
+                        ..Default::default()
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let _ = fs::remove_file(&path);
+    items
+}
+
+fn parse_destination(line: &str) -> Option<PathBuf> {
+    line.split_once("Destination:")
+        .map(|(_, path)| PathBuf::from(path.trim()))
+}
+
+// "[download] Downloading item 3 of 10", older yt-dlp says "video"
+fn parse_playlist_item(line: &str) -> Option<(usize, usize)> {
+    let (_, rest) = line.split_once("Downloading item ")
+        .or_else(|| line.split_once("Downloading video "))?;
+    let (index, count) = rest.trim().split_once(" of ")?;
+    Some((index.trim().parse().ok()?, count.trim().parse().ok()?))
+}
+
+fn emit_item_progress(app: &AppHandle, download_id: &str, filename: &str, item: &PlaylistProgress) {
+    app.emit("download-item-progress", serde_json::json!({
+        "id": download_id,
+        "filename": filename,
+        "index": item.index,
+        "count": item.count,
+        "title": item.title,
+        "path": item.path,
+        "percent": item.percent,
+        "aggregate_percent": item.aggregate_percent()
+    })).ok();
+}
+
+// Read yt-dlp output for a registered download and forward it as events
+fn monitor_download(
+    app: AppHandle,
+    download_id: String,
+    pid: u32,
+    stdout: ChildStdout,
+    stderr: Option<ChildStderr>,
+    kind: DownloadKind,
+) {
+    std::thread::spawn(move || {
+        let registry = app.state::<DownloadRegistry>();
+        let mut filename = registry.get(&download_id)
+            .map(|info| info.filename)
+            .unwrap_or_default();
+        let mut playlist_item: Option<PlaylistProgress> = None;
+
+        // Drain stderr on its own thread so yt-dlp never blocks on a full
+        // pipe while stdout is being read
+        let stderr_reader = stderr.map(|stderr| {
+            let app = app.clone();
+            let download_id = download_id.clone();
+            std::thread::spawn(move || {
+                let mut stderr_log = String::new();
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    // Skip non-critical errors
+                    if line.contains("The downloaded file is empty") {
+                        // This happens when file already exists, ignore it
+                        continue;
+                    }
+                    stderr_log.push_str(&line);
+                    stderr_log.push('\n');
+                    // Only show critical errors
+                    if line.contains("ERROR") && !line.contains("has already been downloaded") {
+                        let filename = app.state::<DownloadRegistry>().get(&download_id)
+                            .map(|info| info.filename)
+                            .unwrap_or_default();
+                        app.emit("download-status", serde_json::json!({
+                            "id": download_id.clone(),
+                            "filename": filename,
+                            "status": format!("Error: {}", line),
+                            "percent": 0.0
+                        })).ok();
+                    }
+                }
+                stderr_log
+            })
+        });
+
+        let reader = BufReader::new(stdout);
+
+        for line in reader.lines().map_while(Result::ok) {
+            // Stream all output for real-time status
+            if !line.starts_with('[') {
+                continue;
+            }
+            let status = if line.contains("[Merger]") || line.contains("Merging") {
+                // Skip merging status - don't emit it
+                continue;
+            } else if let Some(name) = line.strip_prefix("[download] Downloading playlist:") {
+                // Report the whole job under the playlist's name
+                filename = name.trim().to_string();
+                registry.update(&download_id, |info| info.filename = filename.clone());
+                "Loading playlist..."
+            } else if let Some((index, count)) = parse_playlist_item(&line) {
+                let item = PlaylistProgress::new(index, count);
+                let percent = item.aggregate_percent();
+                registry.update(&download_id, |info| {
+                    info.playlist = Some(item.clone());
+                    info.percent = percent;
+                });
+                emit_item_progress(&app, &download_id, &filename, &item);
+                playlist_item = Some(item);
+                app.emit("download-status", serde_json::json!({
+                    "id": download_id.clone(),
+                    "filename": filename.clone(),
+                    "status": format!("Downloading item {} of {}", index, count),
+                    "percent": percent
+                })).ok();
+                continue;
+            } else if line.contains("[download] Destination:") {
+                if let Some(destination) = parse_destination(&line) {
+                    // Merged formats announce one destination per stream,
+                    // the item itself only advances on "Downloading item"
+                    if let Some(item) = &mut playlist_item {
+                        if item.title.is_none() {
+                            item.title = current_item_title(&download_id);
+                        }
+                        item.path = Some(destination.to_string_lossy().to_string());
+                        emit_item_progress(&app, &download_id, &filename, item);
+                    }
+                    let destination = destination.to_string_lossy().to_string();
+                    registry.update(&download_id, |info| {
+                        if !info.destinations.contains(&destination) {
+                            info.destinations.push(destination);
+                        }
+                        info.playlist.clone_from(&playlist_item);
+                    });
+                }
+                "Starting download..."
+            } else if line.contains("[download]") && line.contains('%') {
+                // This is actual progress
+                if let Some(mut progress) = parse_progress(&line) {
+                    progress.filename = Some(filename.clone());
+                    progress.id = Some(download_id.clone());
+                    // Within a playlist the item restarts at 0%, report the
+                    // whole job's progress and the item's separately
+                    if let Some(item) = &mut playlist_item {
+                        item.percent = progress.percent;
+                        progress.percent = item.aggregate_percent();
+                        emit_item_progress(&app, &download_id, &filename, item);
+                    }
+                    registry.update(&download_id, |info| {
+                        if info.state == DownloadState::Starting {
+                            info.state = DownloadState::Downloading;
+                        }
+                        info.percent = progress.percent;
+                        info.playlist.clone_from(&playlist_item);
+                    });
+                    app.emit("download-progress", &progress).ok();
+                }
+                continue; // Skip status update for progress lines
+            } else if line.contains("has already been recorded in the archive") {
+                registry.update(&download_id, |info| info.skipped += 1);
+                "Already downloaded, skipping..."
+            } else if line.contains("does not pass filter") || line.contains("upload date is not in range") {
+                registry.update(&download_id, |info| info.filtered += 1);
+                "Skipped by filter"
+            } else if line.contains("has already been downloaded") {
+                // Completion is reported once the process exits
+                continue;
+            } else if let Some(status) = kind.status_for(&line) {
+                status
+            } else {
+                // Skip other technical messages
+                continue;
+            };
+
+            // Emit status update
+            app.emit("download-status", serde_json::json!({
+                "id": download_id.clone(),
+                "filename": filename.clone(),
+                "status": status,
+                "percent": 0.0
+            })).ok();
+        }
+
+        let stderr_log = stderr_reader
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default();
+
+        // A cancelled or stopped download already had its child reaped
+        let Some(mut child) = registry.take_child_if_pid(&download_id, pid) else {
+            return;
+        };
+        let success = child.wait().map(|status| status.success()).unwrap_or(false);
+        let finished_items = take_finished_items(&download_id);
+        let _ = fs::remove_file(item_titles_file(&download_id));
+        let mut output_paths: Vec<String> = finished_items.iter()
+            .map(|item| item.filepath.clone())
+            .collect();
+        // Older yt-dlp builds may not print the final path
+        if output_paths.is_empty() {
+            output_paths.extend(registry.get(&download_id).and_then(|info| info.destinations.last().cloned()));
+        }
+        let job = registry.job(&download_id);
+        let clip = job.as_ref().and_then(|job| job.clip.clone());
+        let chapter_split = job.and_then(|job| job.chapter_split);
+
+        let stopped = registry.get(&download_id)
+            .is_some_and(|info| info.state.is_terminal() || info.state == DownloadState::Paused);
+        if !success && !stopped {
+            let sections_failed = clip.as_ref().is_some_and(|clip| !clip.trim_after_download)
+                && clip::sections_unsupported(&stderr_log);
+            if sections_failed {
+                // The extractor can't download sections, fetch the whole
+                // file and cut it with ffmpeg instead
+                println!("Section download failed for {}, retrying with ffmpeg trim", download_id);
+                registry.update_job(&download_id, |job| {
+                    if let Some(clip) = &mut job.clip {
+                        clip.fall_back_to_trim(&mut job.args);
+                    }
+                    job.info.state = DownloadState::Queued;
+                    job.info.pid = None;
+                    job.info.percent = 0.0;
+                    job.info.destinations.clear();
+                });
+                app.emit("download-status", serde_json::json!({
+                    "id": download_id.clone(),
+                    "filename": filename.clone(),
+                    "status": "Retrying full download to cut the clip...",
+                    "percent": 0.0
+                })).ok();
+                queue::schedule(&app);
+                return;
+            }
+        }
+
+        // Post-processing of our own, a failure fails the download
+        let mut post_error = None;
+
+        // Sections that were downloaded in full are cut now
+        if let Some(clip) = clip.filter(|clip| success && clip.trim_after_download) {
+            app.emit("download-status", serde_json::json!({
+                "id": download_id.clone(),
+                "filename": filename.clone(),
+                "status": "Cutting clip...",
+                "percent": 100.0
+            })).ok();
+            let mut trimmed = Vec::new();
+            for path in &output_paths {
+                match clip::trim_file(Path::new(path), &clip) {
+                    Ok(paths) => trimmed.extend(paths),
+                    Err(e) => {
+                        post_error = Some(e);
+                        break;
+                    }
+                }
+            }
+            output_paths = trimmed;
+        }
+
+        // Split every finished file that has chapters
+        if let Some(split) = chapter_split.filter(|_| success && post_error.is_none()) {
+            app.emit("download-status", serde_json::json!({
+                "id": download_id.clone(),
+                "filename": filename.clone(),
+                "status": "Splitting chapters...",
+                "percent": 100.0
+            })).ok();
+            let template = app.state::<SettingsStore>().get().chapter_template;
+            let mut split_paths = Vec::new();
+            for item in &finished_items {
+                let item_chapters = chapters::parse_chapters(&item.chapters);
+                if item_chapters.is_empty() {
+                    split_paths.push(item.filepath.clone());
+                    continue;
+                }
+                match split.split_file(Path::new(&item.filepath), &item_chapters, &template) {
+                    Ok(paths) => split_paths.extend(paths),
+                    Err(e) => {
+                        post_error = Some(e);
+                        break;
+                    }
+                }
+            }
+            if !split_paths.is_empty() {
+                output_paths = split_paths;
+            }
+        }
+
+        let mut completed = None;
+        let mut failed = None;
+        registry.update(&download_id, |info| {
+            if info.state.is_terminal() || info.state == DownloadState::Paused {
+                return;
+            }
+            info.pid = None;
+            if let Some(error) = post_error {
+                info.state = DownloadState::Failed;
+                info.error = Some(error);
+                failed = info.error.clone();
+            } else if success {
+                info.state = DownloadState::Completed;
+                info.percent = 100.0;
+                info.output_paths = output_paths;
+                info.filesize = info.output_paths.iter()
+                    .filter_map(|path| fs::metadata(path).ok())
+                    .map(|metadata| metadata.len())
+                    .reduce(|a, b| a + b);
+                completed = Some(info.clone());
+            } else {
+                info.state = DownloadState::Failed;
+                info.error = Some(classify_ytdlp_error(&stderr_log));
+                failed = info.error.clone();
+            }
+        });
+
+        if let Some(error) = failed {
+            app.emit("download-failed", serde_json::json!({
+                "id": download_id,
+                "filename": filename,
+                "error": error
+            })).ok();
+        }
+
+        if let Some(info) = completed {
+            // No file when every item was skipped, say so instead of
+            // pointing at a path that doesn't exist
+            let path = info.output_paths.last().cloned();
+            if path.is_none() {
+                let status = if info.skipped == 0 && info.filtered > 0 {
+                    "Skipped by filter"
+                } else {
+                    "Already downloaded"
+                };
+                app.emit("download-status", serde_json::json!({
+                    "id": info.id,
+                    "filename": filename,
+                    "status": status,
+                    "percent": 100.0
+                })).ok();
+            }
+            let container = path.as_ref()
+                .and_then(|path| Path::new(path).extension())
+                .map(|ext| ext.to_string_lossy().to_string());
+            app.emit("download-complete", serde_json::json!({
+                "id": info.id,
+                "filename": filename,
+                "path": path,
+                "paths": info.output_paths,
+                "size": info.filesize,
+                "container": container,
+                "skipped": info.skipped,
+                "filtered": info.filtered
+            })).ok();
+
+            if let Some(job) = registry.job(&download_id) {
+                history::record(&app, &job, &finished_items);
+            }
+        }
+
+        // A slot just freed up
+        queue::schedule(&app);
+    });
+}
+
+// Cancel a download, killing yt-dlp and its children if it is running
+#[tauri::command]
+pub async fn cancel_download(
+    app: AppHandle,
+    registry: State<'_, DownloadRegistry>,
+    id: String,
+) -> Result<(), AppError> {
+    println!("Cancelling download: {}", id);
+    let info = registry.cancel(&id)?;
+    app.emit("download-cancelled", serde_json::json!({
+        "id": info.id,
+        "filename": info.filename,
+    })).ok();
+    queue::schedule(&app);
+    Ok(())
+}
+
+// Pause a download. Defaults to suspending the process where the
+// platform allows it, otherwise stops it and keeps the .part file.
+#[tauri::command]
+pub async fn pause_download(
+    app: AppHandle,
+    registry: State<'_, DownloadRegistry>,
+    id: String,
+    mode: Option<PauseMode>,
+) -> Result<(), AppError> {
+    println!("Pausing download: {} ({:?})", id, mode);
+    let info = registry.pause(&id, mode.unwrap_or(PauseMode::Suspend))?;
+    app.emit("download-status", serde_json::json!({
+        "id": info.id,
+        "filename": info.filename,
+        "status": "Paused",
+        "percent": info.percent
+    })).ok();
+    // A paused download no longer holds a slot
+    queue::schedule(&app);
+    Ok(())
+}
+
+// Resume a paused download under the same download_id
+#[tauri::command]
+pub async fn resume_download(
+    app: AppHandle,
+    registry: State<'_, DownloadRegistry>,
+    id: String,
+) -> Result<(), AppError> {
+    println!("Resuming download: {}", id);
+    let info = {
+        let mut entries = registry.entries.lock().unwrap();
+        let entry = entries.get_mut(&id)
+            .ok_or_else(|| AppError::not_found(format!("Download not found: {}", id)))?;
+        if entry.job.info.state != DownloadState::Paused {
+            return Err(AppError::invalid(format!("Download is not paused: {}", id)));
+        }
+        // Back through the queue so resuming never exceeds max_concurrent.
+        // Suspended processes are continued when their slot comes up,
+        // stopped ones relaunch with continue semantics.
+        entry.job.info.state = DownloadState::Queued;
+        entry.job.info.clone()
+    };
+
+    app.emit("download-status", serde_json::json!({
+        "id": info.id,
+        "filename": info.filename,
+        "status": "Resuming download...",
+        "percent": info.percent
+    })).ok();
+    queue::schedule(&app);
+    if registry.get(&id).map(|info| info.state) == Some(DownloadState::Queued) {
+        app.emit("download-status", serde_json::json!({
+            "id": info.id,
+            "filename": info.filename,
+            "status": "Waiting in queue...",
+            "percent": info.percent
+        })).ok();
+    }
+    Ok(())
+}
+
+// List all downloads known in this session, queued ones included
+#[tauri::command]
+pub async fn list_downloads(registry: State<'_, DownloadRegistry>) -> Result<Vec<DownloadInfo>, AppError> {
+    Ok(registry.list())
+}
+
+// Get a single download by id
+#[tauri::command]
+pub async fn get_download(registry: State<'_, DownloadRegistry>, id: String) -> Result<DownloadInfo, AppError> {
+    registry.get(&id).ok_or_else(|| AppError::not_found(format!("Download not found: {}", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn resume_args_continue_partial_files() {
+        let args = strings(&["--newline", "--force-overwrites", "-o", "out.%(ext)s", "https://example.com/v"]);
+        let resumed = resume_args(&args);
+        assert_eq!(resumed, strings(&["--newline", "-o", "out.%(ext)s", "--continue", "https://example.com/v"]));
+        // Resuming twice doesn't add --continue again
+        assert_eq!(resume_args(&resumed), resumed);
+    }
+
+    #[test]
+    fn parses_playlist_items() {
+        assert_eq!(parse_playlist_item("[download] Downloading item 3 of 10"), Some((3, 10)));
+        assert_eq!(parse_playlist_item("[download] Downloading video 1 of 2"), Some((1, 2)));
+        assert_eq!(parse_playlist_item("[download] Downloading playlist: Mix"), None);
+        assert_eq!(parse_playlist_item("[download] Downloading item x of 10"), None);
+    }
+
+    #[test]
+    fn aggregates_playlist_percent() {
+        let mut item = PlaylistProgress::new(1, 4);
+        assert_eq!(item.aggregate_percent(), 0.0);
+        item.percent = 50.0;
+        assert_eq!(item.aggregate_percent(), 12.5);
+        let mut item = PlaylistProgress::new(4, 4);
+        item.percent = 100.0;
+        assert_eq!(item.aggregate_percent(), 100.0);
+        let mut empty = PlaylistProgress::new(1, 0);
+        empty.percent = 30.0;
+        assert_eq!(empty.aggregate_percent(), 30.0);
+    }
+
+    #[test]
+    fn queues_by_priority_then_fifo() {
+        let mut entries = HashMap::new();
+        for (seq, (id, priority, state)) in [
+            ("a", 0, DownloadState::Queued),
+            ("b", 5, DownloadState::Queued),
+            ("c", 0, DownloadState::Downloading),
+            ("d", 0, DownloadState::Queued),
+            ("e", 5, DownloadState::Queued),
+            ("f", -1, DownloadState::Queued),
+        ].into_iter().enumerate() {
+            let mut info = DownloadInfo::new(id, "https://example.com", id, Path::new("/tmp"), DownloadKind::Universal);
+            info.priority = priority;
+            info.state = state;
+            entries.insert(id.to_string(), DownloadEntry {
+                job: DownloadJob::new(info, Vec::new(), None),
+                child: None,
+                suspended: false,
+                seq: seq as u64,
+            });
+        }
+        assert_eq!(queued_order(&entries), ["b", "e", "a", "d", "f"]);
+    }
+}
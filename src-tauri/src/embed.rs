@@ -0,0 +1,178 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+
+use crate::chapters::Chapter;
+use crate::error::{AppError, ErrorCode};
+use crate::get_ffmpeg_path;
+
+// Containers ffmpeg can tag, cover art isn't supported for Ogg based ones
+const TAGGABLE: &[&str] = &["mp4", "m4a", "mov", "mp3", "opus", "ogg", "mkv", "mka", "webm"];
+const NO_COVER: &[&str] = &["opus", "ogg", "webm"];
+
+// What yt-dlp writes into downloaded files
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct EmbedOptions {
+    // Title, uploader, date and description tags
+    pub metadata: bool,
+    pub thumbnail: bool,
+    pub chapters: bool,
+}
+
+impl EmbedOptions {
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.metadata {
+            args.push("--embed-metadata".to_string());
+        }
+        if self.thumbnail {
+            // MP4 and MP3 can't hold the WebP thumbnails most sites serve
+            args.extend([
+                "--embed-thumbnail".to_string(),
+                "--convert-thumbnails".to_string(),
+                "jpg".to_string(),
+            ]);
+        }
+        if self.chapters {
+            args.push("--embed-chapters".to_string());
+        }
+        args
+    }
+}
+
+// Tags to write into a local file, empty fields are left alone
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct FileMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub date: Option<String>,
+    pub description: Option<String>,
+    // Image path or URL
+    pub cover: Option<String>,
+    pub chapters: Vec<Chapter>,
+}
+
+// Escape a value for ffmpeg's ffmetadata format
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Chapter list in ffmetadata format, times in milliseconds
+fn chapters_ffmetadata(chapters: &[Chapter]) -> Result<String, AppError> {
+    let mut text = String::from(";FFMETADATA1\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        let end = chapter.end_time
+            .or_else(|| chapters.get(i + 1).map(|next| next.start_time))
+            .ok_or_else(|| AppError::invalid(format!("Chapter {} needs an end time", chapter.index)))?;
+        if end <= chapter.start_time {
+            return Err(AppError::invalid(format!("Chapter {} ends before it starts", chapter.index)));
+        }
+        text.push_str(&format!(
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            (chapter.start_time * 1000.0).round() as i64,
+            (end * 1000.0).round() as i64,
+            escape_ffmetadata(&chapter.title),
+        ));
+    }
+    Ok(text)
+}
+
+// Write tags, cover art and chapters into a file with ffmpeg, without
+// re-encoding the media. Returns warnings about what was left out.
+pub fn embed_into_file(path: &Path, metadata: &FileMetadata) -> Result<Vec<String>, AppError> {
+    if !path.is_file() {
+        return Err(AppError::not_found(format!("File not found: {}", path.display())));
+    }
+    let ext = path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !TAGGABLE.contains(&ext.as_str()) {
+        return Err(AppError::invalid(format!("Can't embed metadata into .{} files", ext)));
+    }
+    let stem = path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_output = path.with_file_name(format!("{}.embedding.{}", stem, ext));
+    let chapters_file = path.with_file_name(format!("{}.chapters.txt", stem));
+
+    let mut command = Command::new(get_ffmpeg_path());
+    command.arg("-y");
+    let mut inputs = 1;
+    let mut warnings = Vec::new();
+
+    // The cover goes first so it is output stream 0 whatever the media has
+    let cover = metadata.cover.as_ref().filter(|_| {
+        let supported = !NO_COVER.contains(&ext.as_str());
+        if !supported {
+            let warning = format!("Cover art was skipped, .{} files can't hold it", ext);
+            println!("{}", warning);
+            warnings.push(warning);
+        }
+        supported
+    });
+    if let Some(cover) = cover {
+        command.args(["-i", cover]);
+        inputs += 1;
+    }
+    command.arg("-i").arg(path);
+    let media_input = inputs - 1;
+
+    if !metadata.chapters.is_empty() {
+        fs::write(&chapters_file, chapters_ffmetadata(&metadata.chapters)?)?;
+        command.args(["-f", "ffmetadata", "-i"]).arg(&chapters_file);
+        command.args(["-map_chapters", &inputs.to_string()]);
+    }
+
+    if cover.is_some() {
+        command.args(["-map", "0", "-map", &media_input.to_string()])
+            .args(["-c", "copy", "-c:0", "mjpeg", "-disposition:0", "attached_pic"])
+            .args(["-map_metadata", &media_input.to_string()]);
+    } else {
+        command.args(["-map", "0", "-c", "copy"]);
+    }
+
+    let tags = [
+        ("title", &metadata.title),
+        ("artist", &metadata.artist),
+        ("date", &metadata.date),
+        ("description", &metadata.description),
+        ("comment", &metadata.description),
+    ];
+    for (key, value) in tags {
+        if let Some(value) = value {
+            command.args(["-metadata", &format!("{}={}", key, value)]);
+        }
+    }
+    if ext == "mp3" {
+        command.args(["-id3v2_version", "3"]);
+    }
+    command.arg(&temp_output);
+
+    let result = command.output().map_err(|e| AppError::spawn("ffmpeg", e));
+    let _ = fs::remove_file(&chapters_file);
+    let result = result?;
+    if !result.status.success() {
+        let _ = fs::remove_file(&temp_output);
+        return Err(AppError::new(ErrorCode::FfmpegFailed, "Failed to embed metadata")
+            .with_stderr(&String::from_utf8_lossy(&result.stderr)));
+    }
+    fs::rename(&temp_output, path)?;
+    Ok(warnings)
+}
+
+// Tag a file that is already on disk, returns warnings to show the user
+#[tauri::command]
+pub async fn embed_metadata(path: String, metadata: FileMetadata) -> Result<Vec<String>, AppError> {
+    println!("Embedding metadata into: {}", path);
+    embed_into_file(Path::new(&path), &metadata)
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+// How many stderr lines we keep for the frontend
+const STDERR_TAIL_LINES: usize = 20;
+
+// Machine readable error code the frontend can branch on
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BinaryNotFound,
+    Network,
+    HttpForbidden,
+    GeoBlocked,
+    LoginRequired,
+    PrivateVideo,
+    UnsupportedUrl,
+    FfmpegFailed,
+    WhisperFailed,
+    ModelMissing,
+    DiskFull,
+    Cancelled,
+    NotFound,
+    InvalidRequest,
+    Other,
+}
+
+// Error returned by every Tauri command
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    // Last lines of the failing tool's stderr, if any
+    pub stderr: Option<String>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        AppError {
+            code,
+            message: message.into(),
+            stderr: None,
+        }
+    }
+
+    pub fn with_stderr(mut self, stderr: &str) -> Self {
+        let tail = stderr_tail(stderr);
+        self.stderr = if tail.is_empty() { None } else { Some(tail) };
+        self
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::InvalidRequest, message)
+    }
+
+    // Failure to start an external tool, a missing binary gets its own code
+    pub fn spawn(binary: &str, e: std::io::Error) -> Self {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AppError::new(ErrorCode::BinaryNotFound, format!("{} was not found", binary))
+        } else {
+            AppError::new(ErrorCode::Other, format!("Failed to run {}: {}", binary, e))
+        }
+    }
+
+    pub fn ffmpeg(stderr: &str) -> Self {
+        AppError::new(ErrorCode::FfmpegFailed, "FFmpeg conversion failed").with_stderr(stderr)
+    }
+
+    pub fn whisper(stderr: &str) -> Self {
+        AppError::new(ErrorCode::WhisperFailed, "whisper.cpp transcription failed").with_stderr(stderr)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::new(ErrorCode::Other, message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::new(ErrorCode::Other, message)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        if is_disk_full(&e.to_string()) {
+            AppError::new(ErrorCode::DiskFull, "Not enough disk space")
+        } else {
+            AppError::new(ErrorCode::Other, e.to_string())
+        }
+    }
+}
+
+fn stderr_tail(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr.lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let start = lines.len().saturating_sub(STDERR_TAIL_LINES);
+    lines[start..].join("\n")
+}
+
+fn is_disk_full(text: &str) -> bool {
+    text.contains("No space left on device") || text.contains("Errno 28") || text.contains("os error 28")
+}
+
+// Map yt-dlp stderr to an error code and a message a user can act on.
+// Only ERROR lines are considered, warnings are noise for this purpose.
+pub fn classify_ytdlp_error(stderr: &str) -> AppError {
+    let error_line = stderr.lines()
+        .rev()
+        .find(|line| line.contains("ERROR"))
+        .unwrap_or("")
+        .to_string();
+    let lower = error_line.to_lowercase();
+
+    let (code, message) = if is_disk_full(&error_line) {
+        (ErrorCode::DiskFull, "Not enough disk space to finish the download")
+    } else if lower.contains("unsupported url") {
+        (ErrorCode::UnsupportedUrl, "This URL is not supported")
+    } else if lower.contains("private video") || lower.contains("video is private") {
+        (ErrorCode::PrivateVideo, "This video is private")
+    } else if lower.contains("available in your country")
+        || lower.contains("geo restrict")
+        || lower.contains("geo-restrict")
+        || lower.contains("from your location")
+    {
+        (ErrorCode::GeoBlocked, "This video is not available in your region")
+    } else if lower.contains("sign in")
+        || lower.contains("login required")
+        || lower.contains("log in")
+        || lower.contains("registered users")
+        || lower.contains("requires authentication")
+        || lower.contains("use --cookies")
+    {
+        (ErrorCode::LoginRequired, "This content requires you to be logged in")
+    } else if lower.contains("http error 403") || lower.contains("403: forbidden") {
+        (ErrorCode::HttpForbidden, "The server refused the request (HTTP 403)")
+    } else if lower.contains("ffmpeg") || lower.contains("ffprobe") || lower.contains("postprocessing") {
+        (ErrorCode::FfmpegFailed, "FFmpeg failed while processing the download")
+    } else if lower.contains("unable to download")
+        || lower.contains("http error")
+        || lower.contains("urlopen error")
+        || lower.contains("timed out")
+        || lower.contains("connection")
+        || lower.contains("name or service not known")
+        || lower.contains("temporary failure in name resolution")
+    {
+        (ErrorCode::Network, "Network error while downloading")
+    } else {
+        (ErrorCode::Other, "Download failed")
+    };
+
+    // Prefer yt-dlp's own wording for the generic case
+    let message = if code == ErrorCode::Other && !error_line.is_empty() {
+        error_line.trim_start_matches("ERROR:").trim().to_string()
+    } else {
+        message.to_string()
+    };
+
+    AppError::new(code, message).with_stderr(stderr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_ytdlp_stderr() {
+        let cases = [
+            (
+                "ERROR: [youtube] abc: Unable to download webpage: HTTP Error 403: Forbidden",
+                ErrorCode::HttpForbidden,
+            ),
+            (
+                "ERROR: [youtube] abc: The uploader has not made this video available in your country",
+                ErrorCode::GeoBlocked,
+            ),
+            ("ERROR: [youtube] abc: Private video. Sign in if you've been granted access", ErrorCode::PrivateVideo),
+            (
+                "ERROR: [instagram] abc: Requested content is not available, rate-limit reached or login required. Use --cookies",
+                ErrorCode::LoginRequired,
+            ),
+            ("ERROR: unable to write data: [Errno 28] No space left on device", ErrorCode::DiskFull),
+            ("ERROR: Postprocessing: Conversion failed!", ErrorCode::FfmpegFailed),
+            ("ERROR: Unsupported URL: https://example.com/", ErrorCode::UnsupportedUrl),
+            ("ERROR: [generic] Unable to download webpage: <urlopen error timed out>", ErrorCode::Network),
+        ];
+        for (stderr, code) in cases {
+            assert_eq!(classify_ytdlp_error(stderr).code, code, "{}", stderr);
+        }
+    }
+
+    #[test]
+    fn ignores_warnings_and_uses_last_error_line() {
+        let stderr = "WARNING: [youtube] Sign in to confirm your age\n\
+                      ERROR: [youtube] abc: HTTP Error 403: Forbidden\n\
+                      ERROR: [youtube] abc: Video unavailable. This video is private";
+        let error = classify_ytdlp_error(stderr);
+        assert_eq!(error.code, ErrorCode::PrivateVideo);
+        assert_eq!(error.stderr.as_deref(), Some(stderr));
+    }
+
+    #[test]
+    fn generic_errors_keep_ytdlp_wording() {
+        let error = classify_ytdlp_error("ERROR: [vimeo] 123: Something odd happened");
+        assert_eq!(error.code, ErrorCode::Other);
+        assert_eq!(error.message, "[vimeo] 123: Something odd happened");
+
+        let error = classify_ytdlp_error("");
+        assert_eq!(error.code, ErrorCode::Other);
+        assert_eq!(error.message, "Download failed");
+        assert_eq!(error.stderr, None);
+    }
+}
@@ -0,0 +1,383 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+use chrono::DateTime;
+use roxmltree::{Document, Node};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::downloads::{enqueue_download, DownloadInfo, DownloadKind};
+use crate::error::{AppError, ErrorCode};
+use crate::settings::{Settings, SettingsStore};
+
+const ITUNES_NS: &str = "http://www.itunes.com/dtds/podcast-1.0.dtd";
+
+// Feeds larger than this are refused rather than parsed
+const MAX_FEED_BYTES: u64 = 20 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Feed {
+    pub title: String,
+    pub link: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub image: Option<String>,
+    // In the order the feed lists them, usually newest first
+    pub episodes: Vec<FeedEpisode>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FeedEpisode {
+    // guid or Atom id, falls back to the enclosure or link URL
+    pub id: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub description: Option<String>,
+    // Unix timestamp, None when the feed's date can't be parsed
+    pub published_at: Option<i64>,
+    // Seconds
+    pub duration: Option<f32>,
+    pub enclosure_url: Option<String>,
+    pub enclosure_type: Option<String>,
+    pub enclosure_length: Option<u64>,
+    pub image: Option<String>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn itunes_child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| {
+        n.is_element() && n.tag_name().name() == name && n.tag_name().namespace() == Some(ITUNES_NS)
+    })
+}
+
+// Trimmed text of a child element, None if missing or empty. iTunes
+// elements share local names with plain RSS ones, so those are skipped.
+fn child_text(node: Node, name: &str) -> Option<String> {
+    node.children()
+        .filter(|n| n.is_element() && n.tag_name().name() == name)
+        .find(|n| n.tag_name().namespace() != Some(ITUNES_NS))
+        .and_then(|n| n.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn itunes_text(node: Node, name: &str) -> Option<String> {
+    itunes_child(node, name)
+        .and_then(|n| n.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+// RSS uses RFC 2822 dates, Atom uses RFC 3339
+fn parse_date(text: &str) -> Option<i64> {
+    let text = text.trim();
+    DateTime::parse_from_rfc2822(text)
+        .or_else(|_| DateTime::parse_from_rfc3339(text))
+        .map(|date| date.timestamp())
+        .ok()
+}
+
+// itunes:duration is "HH:MM:SS", "MM:SS" or plain seconds
+fn parse_duration(text: &str) -> Option<f32> {
+    let mut seconds = 0.0;
+    for part in text.trim().split(':') {
+        seconds = seconds * 60.0 + part.trim().parse::<f32>().ok()?;
+    }
+    Some(seconds)
+}
+
+fn parse_rss(channel: Node) -> Feed {
+    let episodes = channel.children()
+        .filter(|n| n.is_element() && n.tag_name().name() == "item")
+        .map(|item| {
+            let enclosure = child(item, "enclosure");
+            let enclosure_url = enclosure.and_then(|n| n.attribute("url")).map(|s| s.to_string());
+            let link = child_text(item, "link");
+            let title = child_text(item, "title")
+                .or_else(|| itunes_text(item, "title"))
+                .unwrap_or_else(|| "Untitled episode".to_string());
+            FeedEpisode {
+                id: child_text(item, "guid")
+                    .or_else(|| enclosure_url.clone())
+                    .or_else(|| link.clone())
+                    .unwrap_or_else(|| title.clone()),
+                title,
+                link,
+                description: child_text(item, "description")
+                    .or_else(|| itunes_text(item, "summary")),
+                published_at: child_text(item, "pubDate").and_then(|date| parse_date(&date)),
+                duration: itunes_text(item, "duration").and_then(|duration| parse_duration(&duration)),
+                enclosure_type: enclosure.and_then(|n| n.attribute("type")).map(|s| s.to_string()),
+                enclosure_length: enclosure.and_then(|n| n.attribute("length")).and_then(|s| s.parse().ok()),
+                enclosure_url,
+                image: itunes_child(item, "image").and_then(|n| n.attribute("href")).map(|s| s.to_string()),
+                season: itunes_text(item, "season").and_then(|s| s.parse().ok()),
+                episode: itunes_text(item, "episode").and_then(|s| s.parse().ok()),
+            }
+        })
+        .collect();
+
+    Feed {
+        title: child_text(channel, "title").unwrap_or_else(|| "Untitled feed".to_string()),
+        link: child_text(channel, "link"),
+        description: child_text(channel, "description"),
+        author: itunes_text(channel, "author")
+            .or_else(|| child_text(channel, "managingEditor")),
+        image: itunes_child(channel, "image").and_then(|n| n.attribute("href")).map(|s| s.to_string())
+            .or_else(|| child(channel, "image").and_then(|image| child_text(image, "url"))),
+        episodes,
+    }
+}
+
+// Atom links: the enclosure carries the media, "alternate" (the default
+// rel) points at the web page
+fn atom_link<'a, 'input>(node: Node<'a, 'input>, rel: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| {
+        n.is_element() && n.tag_name().name() == "link" && n.attribute("rel").unwrap_or("alternate") == rel
+    })
+}
+
+fn parse_atom(feed: Node) -> Feed {
+    let episodes = feed.children()
+        .filter(|n| n.is_element() && n.tag_name().name() == "entry")
+        .map(|entry| {
+            let enclosure = atom_link(entry, "enclosure");
+            let enclosure_url = enclosure.and_then(|n| n.attribute("href")).map(|s| s.to_string());
+            let link = atom_link(entry, "alternate").and_then(|n| n.attribute("href")).map(|s| s.to_string());
+            let title = child_text(entry, "title").unwrap_or_else(|| "Untitled entry".to_string());
+            FeedEpisode {
+                id: child_text(entry, "id")
+                    .or_else(|| enclosure_url.clone())
+                    .or_else(|| link.clone())
+                    .unwrap_or_else(|| title.clone()),
+                title,
+                link,
+                description: child_text(entry, "summary").or_else(|| child_text(entry, "content")),
+                published_at: child_text(entry, "published")
+                    .or_else(|| child_text(entry, "updated"))
+                    .and_then(|date| parse_date(&date)),
+                duration: itunes_text(entry, "duration").and_then(|duration| parse_duration(&duration)),
+                enclosure_type: enclosure.and_then(|n| n.attribute("type")).map(|s| s.to_string()),
+                enclosure_length: enclosure.and_then(|n| n.attribute("length")).and_then(|s| s.parse().ok()),
+                enclosure_url,
+                image: itunes_child(entry, "image").and_then(|n| n.attribute("href")).map(|s| s.to_string()),
+                season: itunes_text(entry, "season").and_then(|s| s.parse().ok()),
+                episode: itunes_text(entry, "episode").and_then(|s| s.parse().ok()),
+            }
+        })
+        .collect();
+
+    Feed {
+        title: child_text(feed, "title").unwrap_or_else(|| "Untitled feed".to_string()),
+        link: atom_link(feed, "alternate").and_then(|n| n.attribute("href")).map(|s| s.to_string()),
+        description: child_text(feed, "subtitle"),
+        author: child(feed, "author").and_then(|author| child_text(author, "name")),
+        image: child_text(feed, "logo").or_else(|| child_text(feed, "icon")),
+        episodes,
+    }
+}
+
+// Parse an RSS 2.0 or Atom document
+pub fn parse_feed(xml: &str) -> Result<Feed, AppError> {
+    let document = Document::parse(xml)
+        .map_err(|e| AppError::invalid(format!("Invalid feed XML: {}", e)))?;
+    let root = document.root_element();
+    match root.tag_name().name() {
+        "rss" => child(root, "channel")
+            .map(parse_rss)
+            .ok_or_else(|| AppError::invalid("RSS feed has no channel")),
+        "feed" => Ok(parse_atom(root)),
+        other => Err(AppError::invalid(format!("Not an RSS or Atom feed: <{}>", other))),
+    }
+}
+
+// Read a feed from an http(s) URL, a file:// URL or a local path
+fn read_feed_source(source: &str, settings: &Settings) -> Result<String, AppError> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(30))
+            .user_agent(&settings.user_agent)
+            .build();
+        let response = agent.get(source).call().map_err(|e| match e {
+            ureq::Error::Status(403, _) => AppError::new(ErrorCode::HttpForbidden, "The server refused the request (HTTP 403)"),
+            ureq::Error::Status(404, _) => AppError::not_found(format!("Feed not found: {}", source)),
+            e => AppError::new(ErrorCode::Network, format!("Failed to fetch feed: {}", e)),
+        })?;
+        // One byte past the limit tells a feed that is too large from one
+        // that is exactly at it
+        let mut xml = String::new();
+        response.into_reader()
+            .take(MAX_FEED_BYTES + 1)
+            .read_to_string(&mut xml)?;
+        if xml.len() as u64 > MAX_FEED_BYTES {
+            return Err(feed_too_large(source));
+        }
+        Ok(xml)
+    } else {
+        let path = Path::new(source.strip_prefix("file://").unwrap_or(source));
+        if !path.exists() {
+            return Err(AppError::not_found(format!("File not found: {}", path.display())));
+        }
+        if fs::metadata(path)?.len() > MAX_FEED_BYTES {
+            return Err(feed_too_large(source));
+        }
+        Ok(fs::read_to_string(path)?)
+    }
+}
+
+fn feed_too_large(source: &str) -> AppError {
+    AppError::invalid(format!(
+        "Feed too large: {} is over {} MB", source, MAX_FEED_BYTES / (1024 * 1024)
+    ))
+}
+
+// Make a feed or episode title usable as a file or folder name
+pub fn safe_file_name(name: &str) -> String {
+    let cleaned: String = name.chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() { '_' } else { c })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').chars().take(120).collect::<String>();
+    if cleaned.is_empty() { "episode".to_string() } else { cleaned }
+}
+
+// List a feed's episodes from a URL or a local file
+#[tauri::command]
+pub async fn get_feed(settings: State<'_, SettingsStore>, source: String) -> Result<Feed, AppError> {
+    println!("Reading feed: {}", source);
+    let xml = read_feed_source(&source, &settings.get())?;
+    parse_feed(&xml)
+}
+
+// Download the enclosures of the given episodes into a folder named after
+// the feed. Returns the download_ids in the same order.
+#[tauri::command]
+pub async fn download_feed_episodes(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    source: String,
+    episode_ids: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    let settings = settings.get();
+    let feed = parse_feed(&read_feed_source(&source, &settings)?)?;
+    let feed_dir = settings.output_dir().join(safe_file_name(&feed.title));
+    fs::create_dir_all(&feed_dir)?;
+
+    let mut download_ids = Vec::new();
+    for episode_id in &episode_ids {
+        let episode = feed.episodes.iter()
+            .find(|episode| &episode.id == episode_id)
+            .ok_or_else(|| AppError::not_found(format!("Episode not found: {}", episode_id)))?;
+        let media_url = episode.enclosure_url.as_ref()
+            .ok_or_else(|| AppError::invalid(format!("Episode has no media file: {}", episode.title)))?;
+
+        // % starts a field in yt-dlp output templates
+        let name = safe_file_name(&episode.title).replace('%', "%%");
+        let output = feed_dir.join(format!("{}.%(ext)s", name));
+        let args = vec![
+            "--no-playlist".to_string(),
+            "--progress".to_string(),
+            "--newline".to_string(),
+            "--user-agent".to_string(),
+            settings.user_agent.clone(),
+            "-o".to_string(),
+            output.to_string_lossy().to_string(),
+            media_url.clone(),
+        ];
+
+        use uuid::Uuid;
+        let download_id = Uuid::new_v4().to_string();
+        app.emit("download-status", serde_json::json!({
+            "id": download_id.clone(),
+            "filename": episode.title.clone(),
+            "status": "Initializing download...",
+            "percent": 0.0
+        })).ok();
+        let info = DownloadInfo::new(&download_id, media_url, &episode.title, &feed_dir, DownloadKind::Universal);
+        enqueue_download(&app, info, args, None);
+        download_ids.push(download_id);
+    }
+    Ok(download_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PODCAST: &str = include_str!("../tests/fixtures/podcast.rss");
+    const ATOM: &str = include_str!("../tests/fixtures/feed.atom");
+
+    #[test]
+    fn parses_podcast_channel() {
+        let feed = parse_feed(PODCAST).unwrap();
+        assert_eq!(feed.title, "Field Recordings");
+        assert_eq!(feed.author.as_deref(), Some("Ada Example"));
+        assert_eq!(feed.image.as_deref(), Some("https://example.com/cover.jpg"));
+        assert_eq!(feed.episodes.len(), 3);
+    }
+
+    #[test]
+    fn parses_podcast_episodes() {
+        let feed = parse_feed(PODCAST).unwrap();
+        let latest = &feed.episodes[0];
+        assert_eq!(latest.id, "field-recordings-0002");
+        assert_eq!(latest.enclosure_url.as_deref(), Some("https://cdn.example.com/ep2.mp3"));
+        assert_eq!(latest.enclosure_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(latest.enclosure_length, Some(24_000_000));
+        assert_eq!(latest.duration, Some(3723.0));
+        assert_eq!(latest.published_at, Some(1741069800));
+        assert_eq!((latest.season, latest.episode), (Some(1), Some(2)));
+        assert_eq!(latest.description.as_deref(), Some("<p>Gulls &amp; engines.</p>"));
+
+        let first = &feed.episodes[1];
+        assert_eq!(first.duration, Some(754.0));
+        assert_eq!(first.published_at, Some(1738602000));
+        assert_eq!(first.image.as_deref(), Some("https://example.com/ep1.jpg"));
+    }
+
+    #[test]
+    fn tolerates_missing_enclosure_and_bad_date() {
+        let feed = parse_feed(PODCAST).unwrap();
+        let trailer = &feed.episodes[2];
+        assert_eq!(trailer.id, "https://example.com/podcast/trailer");
+        assert_eq!(trailer.enclosure_url, None);
+        assert_eq!(trailer.published_at, None);
+        assert_eq!(trailer.duration, Some(125.0));
+    }
+
+    #[test]
+    fn parses_atom_feed() {
+        let feed = parse_feed(ATOM).unwrap();
+        assert_eq!(feed.title, "Lecture Series");
+        assert_eq!(feed.author.as_deref(), Some("Example University"));
+        assert_eq!(feed.link.as_deref(), Some("https://example.org/lectures"));
+        assert_eq!(feed.episodes.len(), 2);
+
+        let lecture = &feed.episodes[0];
+        assert_eq!(lecture.enclosure_url.as_deref(), Some("https://media.example.org/lecture1.mp4"));
+        assert_eq!(lecture.enclosure_length, Some(52_428_800));
+        assert_eq!(lecture.link.as_deref(), Some("https://example.org/lectures/1"));
+        assert_eq!(lecture.published_at, Some(1745824500));
+
+        // Without <published> the entry's update time is used
+        assert_eq!(feed.episodes[1].published_at, Some(1746100800));
+        assert_eq!(feed.episodes[1].enclosure_url, None);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert_eq!(parse_feed("<html><body/></html>").unwrap_err().code, ErrorCode::InvalidRequest);
+        assert_eq!(parse_feed("not xml").unwrap_err().code, ErrorCode::InvalidRequest);
+    }
+
+    #[test]
+    fn cleans_titles_for_file_names() {
+        assert_eq!(safe_file_name("100% Pure: A/B"), "100% Pure_ A_B");
+        assert_eq!(safe_file_name("  ..  "), "episode");
+    }
+}
@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::settings::Settings;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoCodec {
+    H264,
+    Vp9,
+    Av1,
+}
+
+impl VideoCodec {
+    // Name in yt-dlp's vcodec sort order
+    fn sort_name(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "h264",
+            VideoCodec::Vp9 => "vp9",
+            VideoCodec::Av1 => "av01",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Container {
+    #[default]
+    Mp4,
+    Mkv,
+    Webm,
+}
+
+impl Container {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mkv => "mkv",
+            Container::Webm => "webm",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioCodec {
+    // Whatever the site serves best, no conversion
+    #[default]
+    Best,
+    Mp3,
+    M4a,
+    Aac,
+    Opus,
+    Vorbis,
+    Flac,
+    Alac,
+    Wav,
+}
+
+impl AudioCodec {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AudioCodec::Best => "best",
+            AudioCodec::Mp3 => "mp3",
+            AudioCodec::M4a => "m4a",
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "opus",
+            AudioCodec::Vorbis => "vorbis",
+            AudioCodec::Flac => "flac",
+            AudioCodec::Alac => "alac",
+            AudioCodec::Wav => "wav",
+        }
+    }
+
+    // Source codec that only needs remuxing into this format, as named
+    // in yt-dlp's acodec sort order and the acodec field prefix
+    fn source_codec(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            AudioCodec::M4a | AudioCodec::Aac => Some(("aac", "mp4a")),
+            AudioCodec::Opus => Some(("opus", "opus")),
+            AudioCodec::Vorbis => Some(("vorbis", "vorbis")),
+            AudioCodec::Mp3 => Some(("mp3", "mp3")),
+            AudioCodec::Flac => Some(("flac", "flac")),
+            _ => None,
+        }
+    }
+
+    fn is_lossless(&self) -> bool {
+        matches!(self, AudioCodec::Flac | AudioCodec::Alac | AudioCodec::Wav)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AudioRequest {
+    pub codec: AudioCodec,
+    // Constant bitrate in kbit/s
+    pub bitrate: Option<u32>,
+    // Variable bitrate quality, 0 (best) to 10. Without either the
+    // audio_quality setting is used.
+    pub vbr_quality: Option<u8>,
+    // Resample to this rate in Hz
+    pub sample_rate: Option<u32>,
+    // Keep the source stream as is, only sources already in the codec
+    // are picked. With codec "best" whatever the site serves is kept.
+    pub no_reencode: bool,
+}
+
+impl AudioRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.bitrate.is_some() && self.vbr_quality.is_some() {
+            return Err(AppError::invalid("Choose either a constant bitrate or a VBR quality"));
+        }
+        if self.bitrate == Some(0) || self.sample_rate == Some(0) {
+            return Err(AppError::invalid("Bitrate and sample rate must be greater than zero"));
+        }
+        if self.vbr_quality.is_some_and(|quality| quality > 10) {
+            return Err(AppError::invalid("VBR quality goes from 0 to 10"));
+        }
+        if self.codec.is_lossless() && (self.bitrate.is_some() || self.vbr_quality.is_some()) {
+            return Err(AppError::invalid(format!("{} is lossless and has no bitrate", self.codec.as_str())));
+        }
+        if self.no_reencode {
+            if self.bitrate.is_some() || self.vbr_quality.is_some() || self.sample_rate.is_some() {
+                return Err(AppError::invalid("Bitrate and sample rate need re-encoding"));
+            }
+            if self.codec != AudioCodec::Best && self.codec.source_codec().is_none() {
+                return Err(AppError::invalid(format!(
+                    "Sites don't serve {} audio, it always needs re-encoding", self.codec.as_str()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// What to download, turned into yt-dlp -f/-S selectors
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct FormatRequest {
+    // A yt-dlp format id from get_youtube_info, other fields are ignored
+    pub format_id: Option<String>,
+    pub max_height: Option<u32>,
+    pub max_fps: Option<u32>,
+    // Preferred, other codecs are still used when nothing else fits
+    pub codec: Option<VideoCodec>,
+    pub container: Container,
+    // Extract audio only
+    pub audio: Option<AudioRequest>,
+    // Upper bound for the file size in MB
+    pub max_filesize_mb: Option<u64>,
+    // Prefer HDR streams, SDR otherwise
+    pub hdr: bool,
+}
+
+impl FormatRequest {
+    // The format strings the UI used before typed requests
+    pub fn from_legacy(format: Option<String>) -> Self {
+        match format.as_deref() {
+            None => FormatRequest::default(),
+            Some("mp3") => FormatRequest::audio(AudioCodec::Mp3),
+            Some("wav") => FormatRequest::audio(AudioCodec::Wav),
+            Some(id) => FormatRequest {
+                format_id: Some(id.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn audio(codec: AudioCodec) -> Self {
+        FormatRequest {
+            audio: Some(AudioRequest { codec, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    // Size filter, "?" lets formats with an unknown size through
+    fn size_filter(&self) -> String {
+        self.max_filesize_mb
+            .map(|mb| format!("[filesize<?{}M]", mb))
+            .unwrap_or_default()
+    }
+
+    pub fn args(&self, settings: &Settings) -> Result<Vec<String>, AppError> {
+        if self.max_height == Some(0) || self.max_fps == Some(0) || self.max_filesize_mb == Some(0) {
+            return Err(AppError::invalid("Format limits must be greater than zero"));
+        }
+        if let Some(audio) = &self.audio {
+            audio.validate()?;
+            return Ok(self.audio_args(audio, settings));
+        }
+        if let Some(id) = &self.format_id {
+            return Ok(self.format_id_args(id));
+        }
+        if self.container == Container::Webm && self.codec == Some(VideoCodec::H264) {
+            return Err(AppError::invalid("WebM can't hold H.264 video, use MP4 or MKV"));
+        }
+
+        let mut filters = String::new();
+        if self.container == Container::Webm {
+            filters.push_str("[ext=webm]");
+        }
+        if let Some(height) = self.max_height {
+            filters.push_str(&format!("[height<={}]", height));
+        }
+        if let Some(fps) = self.max_fps {
+            filters.push_str(&format!("[fps<=?{}]", fps));
+        }
+        let audio_filter = if self.container == Container::Webm { "[ext=webm]" } else { "" };
+        let selector = format!(
+            "bv*{}{}+ba{}/b{}{}",
+            filters, self.size_filter(), audio_filter, filters, self.size_filter()
+        );
+
+        // Preferences in order, streams already in the container come last
+        // so quality wins and a remux does the rest
+        let mut sort = Vec::new();
+        if !self.hdr {
+            sort.push("hdr:sdr".to_string());
+        }
+        // Resolution and frame rate always rank before codec and container,
+        // otherwise yt-dlp's own res sort would come after them
+        sort.push(self.max_height.map_or("res".to_string(), |height| format!("res:{}", height)));
+        sort.push(self.max_fps.map_or("fps".to_string(), |fps| format!("fps:{}", fps)));
+        if let Some(codec) = self.codec {
+            sort.push(format!("vcodec:{}", codec.sort_name()));
+        }
+        if self.container == Container::Mp4 {
+            sort.push("ext:mp4:m4a".to_string());
+        }
+
+        let mut args = vec!["-f".to_string(), selector, "-S".to_string(), sort.join(",")];
+        args.extend(self.container_args());
+        Ok(args)
+    }
+
+    // Merge into the container and remux single files, never re-encode
+    fn container_args(&self) -> Vec<String> {
+        let container = self.container.as_str().to_string();
+        vec![
+            "--merge-output-format".to_string(),
+            container.clone(),
+            "--remux-video".to_string(),
+            container,
+        ]
+    }
+
+    fn format_id_args(&self, id: &str) -> Vec<String> {
+        let mut args = vec!["-f".to_string()];
+        if id.parse::<i32>().is_ok() {
+            // Numeric ids are often video only, merge with the best audio
+            args.push(format!("{}+bestaudio[ext=m4a]/{}+bestaudio/best", id, id));
+            args.extend(self.container_args());
+        } else {
+            // Pre-muxed formats and custom selectors pass through
+            args.push(id.to_string());
+        }
+        args
+    }
+
+    fn audio_args(&self, audio: &AudioRequest, settings: &Settings) -> Vec<String> {
+        let size = self.size_filter();
+        let source = audio.codec.source_codec();
+        let selector = match source {
+            Some((_, prefix)) if audio.no_reencode => format!("ba[acodec^={}]{}", prefix, size),
+            _ => format!("ba{}/b{}", size, size),
+        };
+        let mut args = vec!["-f".to_string(), selector, "-x".to_string()];
+        // Prefer a source that only needs remuxing
+        if let Some((sort_name, _)) = source {
+            args.push("-S".to_string());
+            args.push(format!("acodec:{}", sort_name));
+        }
+        if audio.codec != AudioCodec::Best {
+            args.push("--audio-format".to_string());
+            args.push(audio.codec.as_str().to_string());
+        }
+        if audio.no_reencode {
+            return args;
+        }
+
+        if !audio.codec.is_lossless() {
+            let quality = match (audio.bitrate, audio.vbr_quality) {
+                (Some(kbps), _) => format!("{}K", kbps),
+                (None, Some(quality)) => quality.to_string(),
+                (None, None) => settings.audio_quality.clone(),
+            };
+            args.push("--audio-quality".to_string());
+            args.push(quality);
+        }
+        if let Some(rate) = audio.sample_rate {
+            args.push("--postprocessor-args".to_string());
+            args.push(format!("ExtractAudio:-ar {}", rate));
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(request: &FormatRequest) -> Vec<String> {
+        request.args(&Settings::default()).unwrap()
+    }
+
+    #[test]
+    fn maps_legacy_formats() {
+        let mp3 = FormatRequest::from_legacy(Some("mp3".to_string()));
+        assert_eq!(mp3.audio.map(|audio| audio.codec), Some(AudioCodec::Mp3));
+        let wav = FormatRequest::from_legacy(Some("wav".to_string()));
+        assert_eq!(wav.audio.map(|audio| audio.codec), Some(AudioCodec::Wav));
+
+        let numeric = FormatRequest::from_legacy(Some("137".to_string()));
+        assert_eq!(numeric.format_id.as_deref(), Some("137"));
+        assert_eq!(args(&numeric), [
+            "-f", "137+bestaudio[ext=m4a]/137+bestaudio/best",
+            "--merge-output-format", "mp4", "--remux-video", "mp4",
+        ]);
+
+        let default = FormatRequest::from_legacy(None);
+        assert!(default.format_id.is_none() && default.audio.is_none());
+        assert_eq!(args(&default), [
+            "-f", "bv*+ba/b",
+            "-S", "hdr:sdr,res,fps,ext:mp4:m4a",
+            "--merge-output-format", "mp4", "--remux-video", "mp4",
+        ]);
+    }
+
+    #[test]
+    fn builds_video_selector_and_sort() {
+        let request = FormatRequest {
+            max_height: Some(1080),
+            max_fps: Some(30),
+            codec: Some(VideoCodec::Av1),
+            max_filesize_mb: Some(500),
+            ..Default::default()
+        };
+        assert_eq!(args(&request), [
+            "-f", "bv*[height<=1080][fps<=?30][filesize<?500M]+ba/b[height<=1080][fps<=?30][filesize<?500M]",
+            // Resolution and frame rate outrank codec and container
+            "-S", "hdr:sdr,res:1080,fps:30,vcodec:av01,ext:mp4:m4a",
+            "--merge-output-format", "mp4", "--remux-video", "mp4",
+        ]);
+
+        let request = FormatRequest {
+            container: Container::Webm,
+            codec: Some(VideoCodec::Vp9),
+            hdr: true,
+            ..Default::default()
+        };
+        assert_eq!(args(&request), [
+            "-f", "bv*[ext=webm]+ba[ext=webm]/b[ext=webm]",
+            "-S", "res,fps,vcodec:vp9",
+            "--merge-output-format", "webm", "--remux-video", "webm",
+        ]);
+    }
+
+    #[test]
+    fn rejects_invalid_video_requests() {
+        let webm_h264 = FormatRequest {
+            container: Container::Webm,
+            codec: Some(VideoCodec::H264),
+            ..Default::default()
+        };
+        assert!(webm_h264.args(&Settings::default()).is_err());
+
+        let zero_height = FormatRequest { max_height: Some(0), ..Default::default() };
+        assert!(zero_height.args(&Settings::default()).is_err());
+    }
+
+    fn audio(audio: AudioRequest) -> FormatRequest {
+        FormatRequest { audio: Some(audio), ..Default::default() }
+    }
+
+    #[test]
+    fn rejects_invalid_audio_requests() {
+        let cases = [
+            AudioRequest { codec: AudioCodec::Flac, bitrate: Some(320), ..Default::default() },
+            AudioRequest { codec: AudioCodec::Wav, vbr_quality: Some(2), ..Default::default() },
+            AudioRequest { codec: AudioCodec::Mp3, bitrate: Some(192), vbr_quality: Some(2), ..Default::default() },
+            AudioRequest { codec: AudioCodec::Mp3, vbr_quality: Some(11), ..Default::default() },
+            AudioRequest { codec: AudioCodec::Mp3, sample_rate: Some(0), ..Default::default() },
+            AudioRequest { codec: AudioCodec::Opus, bitrate: Some(128), no_reencode: true, ..Default::default() },
+            AudioRequest { codec: AudioCodec::Wav, no_reencode: true, ..Default::default() },
+        ];
+        for request in cases {
+            assert!(audio(request.clone()).args(&Settings::default()).is_err(), "{:?}", request);
+        }
+    }
+
+    #[test]
+    fn builds_audio_args() {
+        let no_reencode = audio(AudioRequest { codec: AudioCodec::Opus, no_reencode: true, ..Default::default() });
+        assert_eq!(args(&no_reencode), [
+            "-f", "ba[acodec^=opus]", "-x", "-S", "acodec:opus", "--audio-format", "opus",
+        ]);
+
+        let resampled = audio(AudioRequest {
+            codec: AudioCodec::Mp3,
+            bitrate: Some(192),
+            sample_rate: Some(44100),
+            ..Default::default()
+        });
+        assert_eq!(args(&resampled), [
+            "-f", "ba/b", "-x", "-S", "acodec:mp3", "--audio-format", "mp3",
+            "--audio-quality", "192K",
+            "--postprocessor-args", "ExtractAudio:-ar 44100",
+        ]);
+
+        // Lossless codecs get no quality, without either the setting is used
+        let flac = audio(AudioRequest { codec: AudioCodec::Flac, ..Default::default() });
+        assert_eq!(args(&flac), ["-f", "ba/b", "-x", "-S", "acodec:flac", "--audio-format", "flac"]);
+        let best = audio(AudioRequest::default());
+        assert_eq!(args(&best), ["-f", "ba/b", "-x", "--audio-quality", "0"]);
+    }
+}
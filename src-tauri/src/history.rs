@@ -0,0 +1,223 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::chapters::ChapterSplit;
+use crate::clip::ClipOptions;
+use crate::downloads::{enqueue_job, DownloadInfo, DownloadJob, DownloadKind, FinishedItem};
+use crate::error::AppError;
+
+// Serialises access to history.jsonl
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+// One finished download, stored as a line in history.jsonl
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    pub uploader: Option<String>,
+    // yt-dlp extractor, e.g. "Youtube" or "Instagram"
+    pub site: Option<String>,
+    pub kind: DownloadKind,
+    // Value passed to -f, kept for display
+    pub format: Option<String>,
+    // Full yt-dlp arguments as first queued, so the download can be
+    // re-run as it was
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    // Job options applied around yt-dlp, re-run along with the args
+    #[serde(default)]
+    pub clip: Option<ClipOptions>,
+    #[serde(default)]
+    pub chapter_split: Option<ChapterSplit>,
+    pub output_dir: String,
+    pub output_paths: Vec<String>,
+    pub filesize: Option<u64>,
+    pub completed_at: u64,
+}
+
+impl HistoryEntry {
+    fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        let contains = |field: &Option<String>| {
+            field.as_deref()
+                .map(|value| value.to_lowercase().contains(&query))
+                .unwrap_or(false)
+        };
+        self.title.to_lowercase().contains(&query)
+            || contains(&self.uploader)
+            || contains(&self.site)
+            || self.url.to_lowercase().contains(&query)
+    }
+}
+
+fn history_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    Ok(dir.join("history.jsonl"))
+}
+
+// Read every entry, skipping lines a crash may have left half written
+fn read_entries(path: &Path) -> Vec<HistoryEntry> {
+    fs::read_to_string(path)
+        .map(|content| {
+            content.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn load(app: &AppHandle) -> Result<Vec<HistoryEntry>, AppError> {
+    let path = history_path(app)?;
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    Ok(read_entries(&path))
+}
+
+// Append a finished download to the journal
+pub fn record(app: &AppHandle, job: &DownloadJob, items: &[FinishedItem]) {
+    let info = &job.info;
+    // Nothing new was written, e.g. everything was skipped via the archive
+    if items.is_empty() && info.output_paths.is_empty() {
+        return;
+    }
+    let first = items.first();
+    let entry = HistoryEntry {
+        id: info.id.clone(),
+        url: info.url.clone(),
+        title: first.and_then(|item| item.title.clone())
+            .unwrap_or_else(|| info.filename.clone()),
+        uploader: first.and_then(|item| item.uploader.clone()),
+        site: first.and_then(|item| item.extractor_key.clone()),
+        kind: info.kind,
+        format: job.args.iter()
+            .position(|arg| arg == "-f")
+            .and_then(|i| job.args.get(i + 1))
+            .cloned(),
+        // Jobs restored from an older queue file have no original args
+        args: if job.original_args.is_empty() { job.args.clone() } else { job.original_args.clone() },
+        cwd: job.cwd.clone(),
+        clip: job.clip.clone(),
+        chapter_split: job.chapter_split.clone(),
+        output_dir: info.output_dir.clone(),
+        output_paths: info.output_paths.clone(),
+        filesize: info.filesize,
+        completed_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+
+    if let Err(e) = append(app, &entry) {
+        println!("Failed to record download history: {}", e);
+    }
+}
+
+fn append(app: &AppHandle, entry: &HistoryEntry) -> Result<(), AppError> {
+    let path = history_path(app)?;
+    let line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize history entry: {}", e))?;
+
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+// Newest downloads first
+#[tauri::command]
+pub async fn list_history(
+    app: AppHandle,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    let mut entries = load(&app)?;
+    entries.reverse();
+    Ok(entries.into_iter()
+        .skip(offset.unwrap_or(0))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+// Case-insensitive search over title, uploader, site and URL
+#[tauri::command]
+pub async fn search_history(app: AppHandle, query: String) -> Result<Vec<HistoryEntry>, AppError> {
+    let mut entries = load(&app)?;
+    entries.retain(|entry| entry.matches(&query));
+    entries.reverse();
+    Ok(entries)
+}
+
+// Remove entries from the history, the downloaded files are left alone
+#[tauri::command]
+pub async fn delete_history(app: AppHandle, ids: Vec<String>) -> Result<usize, AppError> {
+    let path = history_path(&app)?;
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let entries = read_entries(&path);
+    let before = entries.len();
+    let kept: Vec<String> = entries.iter()
+        .filter(|entry| !ids.contains(&entry.id))
+        .filter_map(|entry| serde_json::to_string(entry).ok())
+        .collect();
+    let removed = before - kept.len();
+
+    let mut content = kept.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    let tmp_path = path.with_extension("jsonl.tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(removed)
+}
+
+// Queue a past download again with the same format settings.
+// Returns the new download_id.
+#[tauri::command]
+pub async fn redownload_history(app: AppHandle, id: String) -> Result<String, AppError> {
+    let entry = load(&app)?
+        .into_iter()
+        .rev()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| AppError::not_found(format!("History entry not found: {}", id)))?;
+    println!("Re-downloading {} from history", entry.url);
+
+    use uuid::Uuid;
+    let download_id = Uuid::new_v4().to_string();
+    let info = DownloadInfo::new(
+        &download_id,
+        &entry.url,
+        &entry.title,
+        Path::new(&entry.output_dir),
+        entry.kind,
+    );
+    // A fresh run should fetch the whole file again, even if the
+    // library's download archive already lists it
+    let mut args = Vec::new();
+    let mut iter = entry.args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--continue" => {}
+            "--download-archive" => {
+                iter.next();
+            }
+            _ => args.push(arg),
+        }
+    }
+    // A clip that had to fall back to trimming goes straight there
+    if let Some(clip) = entry.clip.as_ref().filter(|clip| clip.trim_after_download) {
+        clip.clone().fall_back_to_trim(&mut args);
+    }
+    let mut job = DownloadJob::new(info, args, entry.cwd);
+    job.clip = entry.clip;
+    job.chapter_split = entry.chapter_split;
+    enqueue_job(&app, job);
+    Ok(download_id)
+}
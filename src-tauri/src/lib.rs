@@ -1,9 +1,41 @@
-use std::process::{Command, Stdio};
-use std::path::PathBuf;
+use std::process::Command;
+use std::path::{Path, PathBuf};
 use std::fs;
-use std::io::{BufRead, BufReader};
 use serde::{Deserialize, Serialize};
-use tauri::{Emitter, Manager, Window};
+use tauri::{Emitter, Manager, State, Window};
+
+mod archive;
+mod chapters;
+mod clip;
+mod downloads;
+mod embed;
+mod error;
+mod formats;
+mod feeds;
+mod history;
+mod models;
+mod playlist;
+mod queue;
+mod settings;
+mod sites;
+mod subscriptions;
+mod subtitles;
+mod transcript;
+mod urls;
+mod whisper;
+
+use error::{classify_ytdlp_error, AppError};
+use chapters::{Chapter, ChapterSplit};
+use clip::ClipOptions;
+use embed::EmbedOptions;
+use formats::FormatRequest;
+use downloads::{enqueue_job, DownloadInfo, DownloadJob, DownloadKind, DownloadRegistry};
+use playlist::PlaylistSelection;
+use settings::{Settings, SettingsStore};
+use sites::SiteProfile;
+use subtitles::SubtitleOptions;
+use transcript::Segment;
+use whisper::Transcription;
 
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -27,6 +59,37 @@ struct VideoInfo {
     uploader: Option<String>,
     view_count: Option<i64>,
     formats: Vec<VideoFormat>,
+    chapters: Vec<Chapter>,
+    description: Option<String>,
+    // YYYYMMDD
+    upload_date: Option<String>,
+    channel_id: Option<String>,
+    like_count: Option<i64>,
+    tags: Vec<String>,
+    categories: Vec<String>,
+    // Language codes with uploaded and automatic subtitles
+    subtitle_languages: Vec<String>,
+    auto_caption_languages: Vec<String>,
+    // "not_live", "is_live", "is_upcoming", "was_live" or "post_live"
+    live_status: Option<String>,
+    age_limit: Option<i64>,
+    webpage_url: Option<String>,
+}
+
+// Strings of a JSON array, e.g. tags
+fn string_list(value: &serde_json::Value) -> Vec<String> {
+    value.as_array()
+        .map(|items| items.iter().filter_map(|item| item.as_str()).map(|s| s.to_string()).collect())
+        .unwrap_or_default()
+}
+
+// Language codes of a yt-dlp subtitles map, sorted
+fn subtitle_languages(value: &serde_json::Value) -> Vec<String> {
+    let mut languages: Vec<String> = value.as_object()
+        .map(|map| map.keys().cloned().collect())
+        .unwrap_or_default();
+    languages.sort();
+    languages
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -40,24 +103,6 @@ struct DownloadProgress {
     filename: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct PlaylistVideo {
-    id: String,
-    title: String,
-    duration: Option<f32>,
-    thumbnail: Option<String>,
-    url: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct PlaylistInfo {
-    title: String,
-    uploader: Option<String>,
-    video_count: usize,
-    videos: Vec<PlaylistVideo>,
-    thumbnail: Option<String>,
-}
-
 // Helper function to get the path to bundled yt-dlp binary
 fn get_ytdlp_path() -> String {
     #[cfg(target_os = "macos")]
@@ -141,109 +186,19 @@ fn get_ffmpeg_path() -> String {
     "ffmpeg".to_string()
 }
 
-// Helper function to get the path to bundled whisper binary
-fn get_whisper_path() -> Result<(PathBuf, PathBuf), String> {
-    #[cfg(target_os = "macos")]
-    {
-        if let Ok(exe_path) = std::env::current_exe() {
-            println!("Executable path for whisper: {:?}", exe_path);
-            
-            // For bundled macOS app: executable is at Contents/MacOS/Grably
-            // Resources are at Contents/Resources/resources/
-            if let Some(macos_dir) = exe_path.parent() {
-                if let Some(contents_dir) = macos_dir.parent() {
-                    let bundled_whisper = contents_dir.join("Resources").join("resources").join("whisper");
-                    let bundled_model = contents_dir.join("Resources").join("resources").join("ggml-base.en.bin");
-                    println!("Checking bundled whisper at: {:?}", bundled_whisper);
-                    if bundled_whisper.exists() && bundled_model.exists() {
-                        println!("Found bundled whisper and model!");
-                        return Ok((bundled_whisper, bundled_model));
-                    }
-                }
-            }
-            
-            // For development
-            if let Some(parent) = exe_path.parent() {
-                let dev_whisper = parent.join("resources").join("whisper");
-                let dev_model = parent.join("resources").join("ggml-base.en.bin");
-                println!("Checking dev whisper at: {:?}", dev_whisper);
-                if dev_whisper.exists() && dev_model.exists() {
-                    println!("Found dev whisper and model!");
-                    return Ok((dev_whisper, dev_model));
-                }
-            }
-        }
-    }
-    
-    // Try CARGO_MANIFEST_DIR for development
-    let dev_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources");
-    let dev_whisper = dev_dir.join("whisper");
-    let dev_model = dev_dir.join("ggml-base.en.bin");
-    if dev_whisper.exists() && dev_model.exists() {
-        println!("Found whisper in CARGO_MANIFEST_DIR!");
-        return Ok((dev_whisper, dev_model));
-    }
-    
-    Err("whisper.cpp or model not found in resources".to_string())
-}
 
 
-// Get playlist info
-#[tauri::command]
-async fn get_playlist_info(url: String) -> Result<PlaylistInfo, String> {
-    println!("Getting playlist info for URL: {}", url);
-    println!("This is a playlist URL, fetching playlist data...");
-    let output = Command::new(&get_ytdlp_path())
-        .args(&[
-            "--flat-playlist",
-            "-J",
-            &url
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run yt-dlp: {}", e))?;
-    
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
-    }
-    
-    let json_str = String::from_utf8_lossy(&output.stdout);
-    let data: serde_json::Value = serde_json::from_str(&json_str)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-    
-    // Extract playlist entries
-    let videos: Vec<PlaylistVideo> = data["entries"].as_array()
-        .map(|arr| {
-            arr.iter().filter_map(|entry| {
-                Some(PlaylistVideo {
-                    id: entry["id"].as_str()?.to_string(),
-                    title: entry["title"].as_str().unwrap_or("Unknown").to_string(),
-                    duration: entry["duration"].as_f64().map(|d| d as f32),
-                    thumbnail: entry["thumbnail"].as_str().map(|s| s.to_string()),
-                    url: entry["url"].as_str().map(|s| s.to_string())
-                        .or_else(|| entry["id"].as_str().map(|id| format!("https://youtube.com/watch?v={}", id))),
-                })
-            }).collect()
-        })
-        .unwrap_or_default();
-    
-    Ok(PlaylistInfo {
-        title: data["title"].as_str().unwrap_or("Unknown Playlist").to_string(),
-        uploader: data["uploader"].as_str().map(|s| s.to_string()),
-        video_count: data["playlist_count"].as_u64().unwrap_or(videos.len() as u64) as usize,
-        videos: videos.into_iter().take(100).collect(), // Limit to first 100 videos
-        thumbnail: data["thumbnail"].as_str().map(|s| s.to_string()),
-    })
-}
 
-// Check if URL is a playlist
+// Check if URL is a playlist, channel or a video opened from a playlist.
+// Anything that doesn't parse as a URL isn't a playlist either
 #[tauri::command]
-async fn is_playlist(url: String) -> Result<bool, String> {
-    Ok(url.contains("playlist?list=") || url.contains("&list="))
+async fn is_playlist(url: String) -> bool {
+    urls::classify(&url).is_ok_and(|classification| classification.is_collection())
 }
 
 // Get video info and available formats
 #[tauri::command]
-async fn get_youtube_info(url: String) -> Result<VideoInfo, String> {
+async fn get_youtube_info(url: String) -> Result<VideoInfo, AppError> {
     println!("Getting info for URL: {}", url);
     let mut args = vec!["-j", "--no-playlist"];
 
@@ -253,10 +208,10 @@ async fn get_youtube_info(url: String) -> Result<VideoInfo, String> {
     let output = Command::new(&get_ytdlp_path())
         .args(&args)
         .output()
-        .map_err(|e| format!("Failed to run yt-dlp: {}", e))?;
+        .map_err(|e| AppError::spawn("yt-dlp", e))?;
     
     if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+        return Err(classify_ytdlp_error(&String::from_utf8_lossy(&output.stderr)));
     }
     
     let json_str = String::from_utf8_lossy(&output.stdout);
@@ -313,21 +268,33 @@ async fn get_youtube_info(url: String) -> Result<VideoInfo, String> {
         uploader: data["uploader"].as_str().map(|s| s.to_string()),
         view_count: data["view_count"].as_i64(),
         formats,
+        chapters: chapters::parse_chapters(&data["chapters"]),
+        description: data["description"].as_str().map(|s| s.to_string()),
+        upload_date: data["upload_date"].as_str().map(|s| s.to_string()),
+        channel_id: data["channel_id"].as_str().map(|s| s.to_string()),
+        like_count: data["like_count"].as_i64(),
+        tags: string_list(&data["tags"]),
+        categories: string_list(&data["categories"]),
+        subtitle_languages: subtitle_languages(&data["subtitles"]),
+        auto_caption_languages: subtitle_languages(&data["automatic_captions"]),
+        live_status: data["live_status"].as_str().map(|s| s.to_string()),
+        age_limit: data["age_limit"].as_i64(),
+        webpage_url: data["webpage_url"].as_str().map(|s| s.to_string()),
     })
 }
 
 // Get simplified format list
 #[tauri::command]
-async fn get_youtube_formats(url: String) -> Result<Vec<String>, String> {
+async fn get_youtube_formats(url: String) -> Result<Vec<String>, AppError> {
     let args = vec!["-F", "--no-playlist", &url];
     
     let output = Command::new(&get_ytdlp_path())
         .args(&args)
         .output()
-        .map_err(|e| format!("Failed to run yt-dlp: {}", e))?;
+        .map_err(|e| AppError::spawn("yt-dlp", e))?;
     
     if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+        return Err(classify_ytdlp_error(&String::from_utf8_lossy(&output.stderr)));
     }
     
     let output_str = String::from_utf8_lossy(&output.stdout);
@@ -341,23 +308,38 @@ async fn get_youtube_formats(url: String) -> Result<Vec<String>, String> {
     Ok(formats)
 }
 
+// yt-dlp arguments for a format preset: "mp3", "wav", a video format id
+// that gets merged with the best audio, or any other -f value
+fn format_args(settings: &Settings, format: Option<String>) -> Result<Vec<String>, AppError> {
+    FormatRequest::from_legacy(format).args(settings)
+}
+
 // Download YouTube video with progress tracking
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn download_youtube(
     window: Window, 
+    settings: State<'_, SettingsStore>,
     url: String, 
     format: Option<String>, 
+    format_request: Option<FormatRequest>,
     output_path: Option<String>,
-    download_playlist: Option<bool>
-) -> Result<String, String> {
-    let downloads_dir = dirs::download_dir()
-        .unwrap_or_else(|| PathBuf::from("/tmp"));
-    
-    // Create Grably folder in Downloads
-    let grably_dir = downloads_dir.join("Grably");
-    if !grably_dir.exists() {
-        std::fs::create_dir_all(&grably_dir).ok();
+    download_playlist: Option<bool>,
+    playlist_selection: Option<PlaylistSelection>,
+    use_archive: Option<bool>,
+    clip: Option<ClipOptions>,
+    chapter_split: Option<ChapterSplit>,
+    embed: Option<EmbedOptions>,
+    subtitles: Option<SubtitleOptions>
+) -> Result<String, AppError> {
+    if let Some(split) = &chapter_split {
+        split.validate()?;
+        if clip.is_some() {
+            return Err(AppError::invalid("Clips can't be split into chapters"));
+        }
     }
+    let settings = settings.get();
+    let grably_dir = settings.output_dir();
     
     let output = output_path.unwrap_or_else(|| {
         grably_dir.join("%(title)s.%(ext)s").to_string_lossy().to_string()
@@ -372,8 +354,11 @@ async fn download_youtube(
         args.push(ffmpeg_path.clone());
     }
     
-    // Only add --no-playlist if we're not downloading a playlist
-    if !download_playlist.unwrap_or(false) {
+    // Only add --no-playlist if we're not downloading a playlist.
+    // Selecting entries implies downloading from the playlist.
+    if let Some(selection) = &playlist_selection {
+        args.extend(selection.args()?);
+    } else if !download_playlist.unwrap_or(false) {
         args.push("--no-playlist".to_string());
     }
     
@@ -385,47 +370,30 @@ async fn download_youtube(
         output.clone(),
     ]);
     
-    // Add format if specified, with smart audio merging
-    if let Some(fmt) = format {
-        // Handle audio format conversions
-        if fmt == "mp3" {
-            args.push("-f".to_string());
-            args.push("bestaudio".to_string());
-            args.push("-x".to_string()); // Extract audio
-            args.push("--audio-format".to_string());
-            args.push("mp3".to_string());
-            args.push("--audio-quality".to_string());
-            args.push("0".to_string()); // Best quality
-        } else if fmt == "wav" {
-            args.push("-f".to_string());
-            args.push("bestaudio".to_string());
-            args.push("-x".to_string()); // Extract audio
-            args.push("--audio-format".to_string());
-            args.push("wav".to_string());
-        } else if fmt.parse::<i32>().is_ok() {
-            // For video-only formats (like 4K), always merge with best audio
-            args.push("-f".to_string());
-            // Try multiple fallback options for maximum compatibility
-            args.push(format!("{}+bestaudio[ext=m4a]/{}+bestaudio/best", fmt, fmt));
-            // Always merge into mp4 for consistency
-            args.push("--merge-output-format".to_string());
-            args.push("mp4".to_string());
-            // Re-encode if needed (for WebM to MP4 conversion)
-            args.push("--recode-video".to_string());
-            args.push("mp4".to_string());
-        } else {
-            // For pre-muxed formats
-            args.push("-f".to_string());
-            args.push(fmt);
-        }
-    } else {
-        args.push("-f".to_string());
-        // Default: best quality mp4 with audio
-        args.push("bestvideo[ext=mp4]+bestaudio[ext=m4a]/best[ext=mp4]/best".to_string());
+    // Skip media this library already has
+    if use_archive.unwrap_or(settings.use_archive) {
+        // One archive per output folder, templates like %(uploader)s/...
+        // still share it
+        args.extend(archive::archive_args(&grably_dir));
+    }
+    
+    // A typed request wins over the older format string
+    match format_request {
+        Some(request) => args.extend(request.args(&settings)?),
+        None => args.extend(format_args(&settings, format)?),
+    }
+    args.extend(embed.unwrap_or_else(|| settings.embed.clone()).args());
+    if let Some(subtitles) = &subtitles {
+        args.extend(subtitles.args(&settings.subtitle_language)?);
     }
     
     args.push(url.clone());
     
+    // Only download the requested parts
+    if let Some(clip) = &clip {
+        clip.apply(&mut args)?;
+    }
+    
     // Generate unique ID for this download
     use uuid::Uuid;
     let download_id = Uuid::new_v4().to_string();
@@ -441,11 +409,16 @@ async fn download_youtube(
         "percent": 0.0
     })).ok();
     
-    // Get the actual title in background (don't wait for it)
+    // Get the actual title in background (don't wait for it). Playlist
+    // downloads take their name from yt-dlp's output instead.
+    let is_playlist_job = args.iter().all(|arg| arg != "--no-playlist");
     let url_for_title = url.clone();
     let window_for_title = window.clone();
     let download_id_for_title = download_id.clone();
     std::thread::spawn(move || {
+        if is_playlist_job {
+            return;
+        }
         let mut title_args = vec!["--get-title"];
         title_args.push(&url_for_title);
 
@@ -455,6 +428,8 @@ async fn download_youtube(
             if let Ok(title) = String::from_utf8(output.stdout) {
                 let title = title.trim().to_string();
                 if !title.is_empty() {
+                    window_for_title.state::<DownloadRegistry>()
+                        .update(&download_id_for_title, |info| info.filename = title.clone());
                     // Update with real title
                     window_for_title.emit("download-status", serde_json::json!({
                         "id": download_id_for_title,
@@ -469,110 +444,12 @@ async fn download_youtube(
     
     let filename = temp_filename; // Use temp filename for now
     
-    // Spawn the download process
-    let mut child = Command::new(&get_ytdlp_path())
-        .args(&args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to start download: {}", e))?;
-    
-    // Get download path for completion notification
-    let download_path = grably_dir.join(&filename);
-    let download_path_str = download_path.to_string_lossy().to_string();
-    
-    // Read all output in background
-    if let Some(stdout) = child.stdout.take() {
-        let stderr = child.stderr.take();
-        std::thread::spawn(move || {
-            let reader = BufReader::new(stdout);
-            let mut completed = false;
-            
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    // Stream all output for real-time status
-                    if line.starts_with("[") {
-                        // Parse different yt-dlp status messages
-                        let status = if line.contains("youtube") || line.contains("Extracting URL") {
-                            "Connecting to YouTube..."
-                        } else if line.contains("Downloading webpage") {
-                            "Loading video page..."
-                        } else if line.contains("Downloading API") || line.contains("Downloading JSON") {
-                            "Fetching video info..."
-                        } else if line.contains("Downloading m3u8") || line.contains("manifest") {
-                            "Processing video streams..."
-                        } else if line.contains("[Merger]") || line.contains("Merging") {
-                            // Skip merging status - don't emit it
-                            continue;
-                        } else if line.contains("[download] Destination:") {
-                            "Starting download..."
-                        } else if line.contains("[download]") && line.contains("%") {
-                            // This is actual progress
-                            if let Some(mut progress) = parse_progress(&line) {
-                                progress.filename = Some(filename.clone());
-                                progress.id = Some(download_id.clone());
-                                window.emit("download-progress", &progress).ok();
-                                
-                                // Check if download completed (only emit once)
-                                if !completed && progress.percent >= 100.0 {
-                                    completed = true;
-                                    window.emit("download-complete", serde_json::json!({
-                                        "filename": filename.clone(),
-                                        "path": download_path_str.clone()
-                                    })).ok();
-                                }
-                            }
-                            continue; // Skip status update for progress lines
-                        } else if line.contains("has already been downloaded") {
-                            if !completed {
-                                completed = true;
-                                window.emit("download-complete", serde_json::json!({
-                                    "filename": filename.clone(),
-                                    "path": download_path_str.clone()
-                                })).ok();
-                            }
-                            continue;
-                        } else {
-                            // Skip other technical messages
-                            continue;
-                        };
-                        
-                        // Emit status update
-                        window.emit("download-status", serde_json::json!({
-                            "id": download_id.clone(),
-                            "filename": filename.clone(),
-                            "status": status,
-                            "percent": 0.0
-                        })).ok();
-                    }
-                }
-            }
-            
-            // Also read stderr for any error messages
-            if let Some(stderr) = stderr {
-                let stderr_reader = BufReader::new(stderr);
-                for line in stderr_reader.lines() {
-                    if let Ok(line) = line {
-                        // Skip non-critical errors
-                        if line.contains("The downloaded file is empty") {
-                            continue;
-                        }
-                        if line.contains("ERROR") {
-                            // Only show critical errors
-                            if !line.contains("has already been downloaded") {
-                                window.emit("download-status", serde_json::json!({
-                                    "id": download_id.clone(),
-                                    "filename": filename.clone(),
-                                    "status": format!("Error: {}", line),
-                                    "percent": 0.0
-                                })).ok();
-                            }
-                        }
-                    }
-                }
-            }
-        });
-    }
+    // Queue the download, it can then be listed, paused and cancelled
+    let info = DownloadInfo::new(&download_id, &url, &filename, &grably_dir, DownloadKind::Youtube);
+    let mut job = DownloadJob::new(info, args, None);
+    job.clip = clip;
+    job.chapter_split = chapter_split;
+    enqueue_job(window.app_handle(), job);
     
     // Return immediately - fire and forget
     Ok(format!("Download started"))
@@ -615,124 +492,144 @@ fn parse_progress(line: &str) -> Option<DownloadProgress> {
     })
 }
 
+// Map yt-dlp log lines to a status for YouTube downloads
+fn youtube_status(line: &str) -> Option<&'static str> {
+    if line.contains("youtube") || line.contains("Extracting URL") {
+        Some("Connecting to YouTube...")
+    } else if line.contains("Downloading webpage") {
+        Some("Loading video page...")
+    } else if line.contains("Downloading API") || line.contains("Downloading JSON") {
+        Some("Fetching video info...")
+    } else if line.contains("Downloading m3u8") || line.contains("manifest") {
+        Some("Processing video streams...")
+    } else {
+        None
+    }
+}
+
+// Map yt-dlp log lines to a status for other sites
+fn universal_status(line: &str) -> Option<&'static str> {
+    if line.contains("Extracting URL") {
+        Some("Extracting URL...")
+    } else if line.contains("Downloading webpage") {
+        Some("Fetching webpage...")
+    } else if line.contains("Downloading API") || line.contains("Downloading JSON") {
+        Some("Accessing API...")
+    } else if line.contains("Downloading video information") {
+        Some("Getting video info...")
+    } else if line.contains("Downloading m3u8") {
+        Some("Processing video streams...")
+    } else {
+        None
+    }
+}
+
 // Transcribe YouTube video (subtitles first, then Whisper)
 #[tauri::command]
-async fn transcribe_youtube(url: String) -> Result<String, String> {
+async fn transcribe_youtube(
+    app: tauri::AppHandle,
+    settings: State<'_, SettingsStore>,
+    url: String,
+    language: Option<String>
+) -> Result<Transcription, AppError> {
     println!("Transcribing YouTube video: {}", url);
+    let settings = settings.get();
+    // Captions can't be detected, "auto" falls back to the subtitle setting
+    let language = language
+        .filter(|language| language != whisper::AUTO_LANGUAGE)
+        .unwrap_or_else(|| settings.subtitle_language.clone());
     
     // Try to get YouTube subtitles ONLY - no fallback
-    match get_youtube_subtitles(&url).await {
-        Ok(transcript) => {
+    match get_youtube_subtitles(&url, &settings, &language).await {
+        Ok(segments) => {
             println!("Got subtitles from YouTube");
-            Ok(transcript)
+            let transcription = Transcription::new(segments, Some(language), None);
+            transcript::save(&app, &transcription)?;
+            Ok(transcription)
         }
         Err(e) => {
             println!("YouTube subtitles not available: {}", e);
             // Return clear error message for the user
-            Err(format!("YouTube captions not available for this video. {}. Try using 'Whisper AI' option instead.", e))
+            Err(AppError::new(
+                e.code,
+                format!("YouTube captions not available for this video. {}. Try using 'Whisper AI' option instead.", e),
+            ))
         }
     }
 }
 
-async fn get_youtube_subtitles(url: &str) -> Result<String, String> {
+async fn get_youtube_subtitles(url: &str, settings: &Settings, subtitle_lang: &str) -> Result<Vec<Segment>, AppError> {
+    // Same identity and cookies as the whisper path, so age or login gated
+    // videos behave the same in both
+    let mut identity_args = vec!["--user-agent", settings.user_agent.as_str()];
+    let cookies_file = settings.cookies_file();
+    if let Some(cookies) = &cookies_file {
+        identity_args.push("--cookies");
+        identity_args.push(cookies);
+    }
+    
     // First get the video ID
-    let id_output = Command::new(&get_ytdlp_path())
-        .args(&[
-            "--print", "id",
-            url
-        ])
+    let id_output = Command::new(get_ytdlp_path())
+        .args(&identity_args)
+        .args(["--print", "id", url])
         .output()
-        .map_err(|e| format!("Failed to get video ID: {}", e))?;
+        .map_err(|e| AppError::spawn("yt-dlp", e))?;
     
     if !id_output.status.success() {
-        return Err("Could not get video ID".to_string());
+        let error = classify_ytdlp_error(&String::from_utf8_lossy(&id_output.stderr));
+        return Err(AppError::new(error.code, "Could not get video ID").with_stderr(&String::from_utf8_lossy(&id_output.stderr)));
     }
     
     let video_id = String::from_utf8_lossy(&id_output.stdout).trim().to_string();
     
     if video_id.is_empty() {
-        return Err("Could not get video ID".to_string());
+        return Err(AppError::not_found("Could not get video ID"));
     }
     
     // Now actually download the subtitles
-    let subtitle_path = format!("/tmp/{}.en.vtt", video_id);
+    let subtitle_path = format!("/tmp/{}.{}.vtt", video_id, subtitle_lang);
     
-    let download_output = Command::new(&get_ytdlp_path())
-        .args(&[
+    let download_output = Command::new(get_ytdlp_path())
+        .args(&identity_args)
+        .args([
             "--skip-download",
             "--write-auto-subs",  // Get auto-generated subtitles
-            "--sub-lang", "en",
+            "--sub-lang", subtitle_lang,
             "--convert-subs", "vtt",  // Convert to VTT format
             "--output", &format!("/tmp/{}", video_id),
             url
         ])
         .output()
-        .map_err(|e| format!("Failed to download subtitles: {}", e))?;
+        .map_err(|e| AppError::spawn("yt-dlp", e))?;
     
     if !download_output.status.success() {
-        return Err("No subtitles available".to_string());
+        return Err(AppError::not_found("No subtitles available")
+            .with_stderr(&String::from_utf8_lossy(&download_output.stderr)));
     }
     
     if let Ok(content) = fs::read_to_string(&subtitle_path) {
-        // Parse VTT to plain text
-        let transcript = parse_vtt_to_text(&content);
+        // Parse VTT cues into timed segments
+        let segments = transcript::parse_vtt(&content);
         
         // Clean up file
         let _ = fs::remove_file(&subtitle_path);
         
-        if !transcript.trim().is_empty() {
-            return Ok(transcript);
-        }
-    }
-    
-    Err("No subtitle files found".to_string())
-}
-
-fn parse_vtt_to_text(vtt_content: &str) -> String {
-    let mut transcript = String::new();
-    let mut last_text = String::new();
-    
-    for line in vtt_content.lines() {
-        // Skip headers, timestamps, and metadata
-        if !line.contains("-->") 
-            && !line.starts_with("WEBVTT")
-            && !line.starts_with("NOTE")
-            && !line.starts_with("Kind:")
-            && !line.starts_with("Language:")
-            && !line.trim().is_empty()
-            && !line.chars().all(|c| c.is_numeric() || c == ':' || c == '.')
-        {
-            // Remove all timestamp tags and HTML tags
-            let mut clean_text = line.to_string();
-            
-            // Remove timestamp tags like <00:00:00.800>
-            while let Some(start) = clean_text.find('<') {
-                if let Some(end) = clean_text.find('>') {
-                    if end > start {
-                        clean_text.replace_range(start..=end, "");
-                    } else {
-                        break;
-                    }
-                } else {
-                    break;
-                }
-            }
-            
-            clean_text = clean_text.trim().to_string();
-            
-            // Avoid duplicates and empty lines
-            if !clean_text.is_empty() && clean_text != last_text {
-                transcript.push_str(&clean_text);
-                transcript.push(' ');
-                last_text = clean_text;
-            }
+        if !segments.is_empty() {
+            return Ok(segments);
         }
     }
     
-    transcript.trim().to_string()
+    Err(AppError::not_found("No subtitle files found"))
 }
 
-async fn transcribe_with_whisper(url: &str) -> Result<String, String> {
+async fn transcribe_with_whisper(
+    url: &str,
+    settings: &Settings,
+    language: &str,
+    model_path: &Path,
+    word_timestamps: bool
+) -> Result<Transcription, AppError> {
+    whisper::validate_language(language)?;
     // Download audio first
     println!("Downloading audio from: {}", url);
     
@@ -773,17 +670,17 @@ async fn transcribe_with_whisper(url: &str) -> Result<String, String> {
     args.extend(vec![
         "-x",
         "--audio-format", "mp3",
-        "--audio-quality", "5",
+        "--audio-quality", &settings.transcription_audio_quality,
         "-o", audio_path_str,
     ]);
 
     // For other platforms, let yt-dlp use its defaults
     
     // Check for cookies file
-    let cookies_path = PathBuf::from("/tmp/cookies.txt");
-    if cookies_path.exists() {
+    let cookies_file = settings.cookies_file();
+    if let Some(cookies) = &cookies_file {
         args.push("--cookies");
-        args.push("/tmp/cookies.txt");
+        args.push(cookies);
     }
     
     // Add the URL at the end
@@ -792,79 +689,54 @@ async fn transcribe_with_whisper(url: &str) -> Result<String, String> {
     let output = Command::new(&get_ytdlp_path())
         .args(&args)
         .output()
-        .map_err(|e| format!("Failed to download audio: {}", e))?;
+        .map_err(|e| AppError::spawn("yt-dlp", e))?;
     
     if !output.status.success() {
         // Clean up even on download failure
         let _ = fs::remove_file(&audio_path);
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to download audio: {}", stderr));
+        return Err(classify_ytdlp_error(&stderr));
     }
     
-    // Get the path to the whisper.cpp binary and model
-    let (whisper_path, model_path) = get_whisper_path().map_err(|e| {
-        let _ = fs::remove_file(&audio_path);
-        e
-    })?;
-    
-    println!("Using whisper.cpp at: {:?}", whisper_path);
-    
-    // Create a temporary output file for transcript
-    let output_file = format!("/tmp/whisper_output_{}", timestamp);
-    
-    // Use whisper.cpp to transcribe
-    let output = Command::new(whisper_path)
-        .args(&[
-            "-m", model_path.to_str().ok_or("Invalid model path")?,
-            "-f", audio_path_str,
-            "-otxt",
-            "-of", &output_file,
-            "--no-timestamps",
-            "-l", "en"
-        ])
-        .output()
-        .map_err(|e| format!("whisper.cpp failed: {}", e))?;
-    
-    if output.status.success() {
-        // Read the transcript file
-        let transcript_path = format!("{}.txt", output_file);
-        let transcript = fs::read_to_string(&transcript_path)
-            .map_err(|e| format!("Failed to read transcript: {}", e))?;
-        
-        // ALWAYS clean up temp files
-        let _ = fs::remove_file(&audio_path);
-        let _ = fs::remove_file(&transcript_path);
-        
-        Ok(transcript.trim().to_string())
-    } else {
-        // Clean up on whisper.cpp failure
-        let _ = fs::remove_file(&audio_path);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("whisper.cpp transcription failed: {}", stderr))
-    }
+    // ALWAYS clean up the audio, whatever whisper.cpp did with it
+    let result = whisper::run(audio_path_str, language, model_path, word_timestamps);
+    let _ = fs::remove_file(&audio_path);
+    result
 }
 
 // Universal download for any supported site
 #[tauri::command]
-async fn download_universal(window: Window, url: String, site_type: Option<String>) -> Result<String, String> {
+#[allow(clippy::too_many_arguments)]
+async fn download_universal(
+    window: Window,
+    settings: State<'_, SettingsStore>,
+    url: String,
+    site_type: Option<String>,
+    use_archive: Option<bool>,
+    clip: Option<ClipOptions>,
+    embed: Option<EmbedOptions>,
+    subtitles: Option<SubtitleOptions>
+) -> Result<String, AppError> {
     println!("Universal download: {} (type: {:?})", url, site_type);
-    
-    let downloads_dir = dirs::download_dir()
-        .unwrap_or_else(|| PathBuf::from("/tmp"));
-    
-    // Create Grably folder in Downloads
-    let grably_dir = downloads_dir.join("Grably");
-    if !grably_dir.exists() {
-        std::fs::create_dir_all(&grably_dir).ok();
+    // Reject bad options before anything is shown as started
+    if let Some(clip) = &clip {
+        clip.ranges()?;
     }
     
-    let mut args = vec![
+    let settings = settings.get();
+    let grably_dir = settings.output_dir();
+    let subtitle_args = subtitles
+        .map(|subtitles| subtitles.args(&settings.subtitle_language))
+        .transpose()?
+        .unwrap_or_default();
+    
+    let args = vec![
         "--no-playlist",
         "--progress",
         "--newline",
         "--force-overwrites",  // Allow re-downloading existing files
         "--user-agent",
-        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+        &settings.user_agent,
         "--add-header",
         "Accept-Language:en-US,en;q=0.9",
         "--add-header",
@@ -875,64 +747,23 @@ async fn download_universal(window: Window, url: String, site_type: Option<Strin
         "--no-warnings",
     ];
     
-    // Check if cookies file exists
-    let cookies_path = PathBuf::from("/tmp/cookies.txt");
-    if cookies_path.exists() {
-        args.push("--cookies");
-        args.push("/tmp/cookies.txt");
-    }
-    
     // Add timestamp to prevent conflicts with simultaneous downloads
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
         .as_millis() % 10000; // Use last 4 digits of timestamp
     
-    // Site-specific handling
-    let output_template = match site_type.as_deref() {
-        Some("instagram") => {
-            // Instagram mobile headers - for public content
-            args.push("--add-header");
-            args.push("X-IG-App-ID:936619743392459");  // Instagram mobile app ID
-            args.push("--add-header");
-            args.push("X-IG-WWW-Claim:0");
-            args.push("--add-header");
-            args.push("X-ASBD-ID:129477");
-            args.push("--add-header");
-            args.push("X-Requested-With:XMLHttpRequest");
-            args.push("--add-header");
-            args.push("Referer:https://www.instagram.com/");
-            args.push("--extractor-args");
-            args.push("instagram:app_id=936619743392459");
-            format!("instagram_%(id)s_{}.%(ext)s", timestamp)
-        }
-        Some("tiktok") => {
-            // TikTok mobile headers work best
-            args.push("--add-header");
-            args.push("Referer:https://www.tiktok.com/");
-            args.push("--extractor-args");
-            args.push("tiktok:app_version=33.6.3");
-            format!("tiktok_%(id)s_{}.%(ext)s", timestamp)
-        }
-        Some("twitter") | Some("x") => {
-            // Twitter needs guest token - yt-dlp handles this automatically
-            args.push("--add-header");
-            args.push("Referer:https://x.com/");
-            args.push("--add-header");
-            args.push("Origin:https://x.com");
-            format!("twitter_%(id)s_{}.%(ext)s", timestamp)
-        }
-        Some("facebook") => {
-            // Facebook needs auth nowadays
-            args.push("--add-header");
-            args.push("Referer:https://www.facebook.com/");
-            format!("facebook_%(id)s_{}.%(ext)s", timestamp)
-        }
-        _ => {
-            // Generic output template
-            format!("%(title)s_{}.%(ext)s", timestamp)
-        }
+    // Site-specific headers, extractor args and cookies come from the
+    // site profiles, an explicit site_type overrides detection
+    let profiles = sites::load_profiles(window.app_handle());
+    let profile = match site_type.as_deref() {
+        Some(site_type) => sites::find(&profiles, site_type)?,
+        None => sites::detect(&profiles, &url)
+            .cloned()
+            .unwrap_or_else(SiteProfile::generic),
     };
+    println!("Using site profile: {}", profile.id);
+    let output_template = profile.output_template(timestamp);
     
     let output_path = grably_dir.join(output_template);
     
@@ -941,7 +772,7 @@ async fn download_universal(window: Window, url: String, site_type: Option<Strin
     let download_id = Uuid::new_v4().to_string();
     
     // Use temporary filename - we'll update it later with real title
-    let temp_filename = format!("{} Download", site_type.as_deref().unwrap_or("Media"));
+    let temp_filename = format!("{} Download", profile.name);
     
     // Immediately emit download started with status - NO DELAY!
     window.emit("download-status", serde_json::json!({
@@ -955,7 +786,6 @@ async fn download_universal(window: Window, url: String, site_type: Option<Strin
     let url_for_title = url.clone();
     let window_for_title = window.clone();
     let download_id_for_title = download_id.clone();
-    let _site_type_for_title = site_type.clone();
     std::thread::spawn(move || {
         let mut title_args = vec!["--get-title"];
         title_args.push(&url_for_title);
@@ -966,6 +796,8 @@ async fn download_universal(window: Window, url: String, site_type: Option<Strin
             if let Ok(title) = String::from_utf8(output.stdout) {
                 let title = title.trim().to_string();
                 if !title.is_empty() {
+                    window_for_title.state::<DownloadRegistry>()
+                        .update(&download_id_for_title, |info| info.filename = title.clone());
                     // Update with real title
                     window_for_title.emit("download-status", serde_json::json!({
                         "id": download_id_for_title,
@@ -981,162 +813,110 @@ async fn download_universal(window: Window, url: String, site_type: Option<Strin
     let filename = temp_filename; // Use temp filename for now
     
     let mut owned_args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    // Add ffmpeg location if using bundled binary, subtitles, embedding
+    // and clips all run through it
+    let ffmpeg_path = get_ffmpeg_path();
+    if ffmpeg_path != "ffmpeg" {
+        owned_args.push("--ffmpeg-location".to_string());
+        owned_args.push(ffmpeg_path);
+    }
+    owned_args.extend(profile.args(&settings));
+    owned_args.extend(embed.unwrap_or_else(|| settings.embed.clone()).args());
+    owned_args.extend(subtitle_args);
+    // Skip media this library already has
+    if use_archive.unwrap_or(settings.use_archive) {
+        owned_args.extend(archive::archive_args(&grably_dir));
+    }
     owned_args.push("-o".to_string());
     owned_args.push(output_path.to_string_lossy().to_string());
+    let source_url = url.clone();
     owned_args.push(url);
     
-    // Get download path for completion notification
-    let download_path_str = output_path.to_string_lossy().to_string();
-    
-    // Spawn the download process
-    let mut child = Command::new(&get_ytdlp_path())
-        .args(&owned_args)
-        .current_dir(&grably_dir)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to start download: {}", e))?;
-    
-    // Read all output in background
-    if let Some(stdout) = child.stdout.take() {
-        let stderr = child.stderr.take();
-        std::thread::spawn(move || {
-            let reader = BufReader::new(stdout);
-            let mut completed = false;
-            
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    // Stream all output for real-time status
-                    if line.starts_with("[") {
-                        // Parse different yt-dlp status messages
-                        let status = if line.contains("Extracting URL") {
-                            "Extracting URL..."
-                        } else if line.contains("Downloading webpage") {
-                            "Fetching webpage..."
-                        } else if line.contains("Downloading API") || line.contains("Downloading JSON") {
-                            "Accessing API..."
-                        } else if line.contains("Downloading video information") {
-                            "Getting video info..."
-                        } else if line.contains("Downloading m3u8") {
-                            "Processing video streams..."
-                        } else if line.contains("[Merger]") || line.contains("Merging") {
-                            // Skip merging status - don't emit it
-                            continue;
-                        } else if line.contains("[download] Destination:") {
-                            "Starting download..."
-                        } else if line.contains("[download]") && line.contains("%") {
-                            // This is actual progress
-                            if let Some(mut progress) = parse_progress(&line) {
-                                progress.filename = Some(filename.clone());
-                                progress.id = Some(download_id.clone());
-                                window.emit("download-progress", &progress).ok();
-                                
-                                // Check if download completed (only emit once)
-                                if !completed && progress.percent >= 100.0 {
-                                    completed = true;
-                                    window.emit("download-complete", serde_json::json!({
-                                        "filename": filename.clone(),
-                                        "path": download_path_str.clone()
-                                    })).ok();
-                                }
-                            }
-                            continue; // Skip status update for progress lines
-                        } else if line.contains("has already been downloaded") {
-                            if !completed {
-                                completed = true;
-                                window.emit("download-complete", serde_json::json!({
-                                    "filename": filename.clone(),
-                                    "path": download_path_str.clone()
-                                })).ok();
-                            }
-                            continue;
-                        } else {
-                            // Skip other technical messages
-                            continue;
-                        };
-                        
-                        // Emit status update
-                        window.emit("download-status", serde_json::json!({
-                            "id": download_id.clone(),
-                            "filename": filename.clone(),
-                            "status": status,
-                            "percent": 0.0
-                        })).ok();
-                    }
-                }
-            }
-            
-            // Also read stderr for any error messages
-            if let Some(stderr) = stderr {
-                let stderr_reader = BufReader::new(stderr);
-                for line in stderr_reader.lines() {
-                    if let Ok(line) = line {
-                        // Skip non-critical errors
-                        if line.contains("The downloaded file is empty") {
-                            // This happens when file already exists, ignore it
-                            continue;
-                        }
-                        if line.contains("ERROR") {
-                            // Only show critical errors
-                            if !line.contains("has already been downloaded") {
-                                window.emit("download-status", serde_json::json!({
-                                    "id": download_id.clone(),
-                                    "filename": filename.clone(),
-                                    "status": format!("Error: {}", line),
-                                    "percent": 0.0
-                                })).ok();
-                            }
-                        }
-                    }
-                }
-            }
-        });
+    // Only download the requested parts
+    if let Some(clip) = &clip {
+        clip.apply(&mut owned_args)?;
     }
     
+    // Queue the download, it can then be listed, paused and cancelled
+    let info = DownloadInfo::new(&download_id, &source_url, &filename, &grably_dir, DownloadKind::Universal);
+    let mut job = DownloadJob::new(info, owned_args, Some(grably_dir.clone()));
+    job.clip = clip;
+    enqueue_job(window.app_handle(), job);
+    
     // Return immediately - fire and forget
     Ok(format!("Download started"))
 }
 
 // Transcribe TikTok video
 #[tauri::command]
-async fn transcribe_tiktok(url: String) -> Result<String, String> {
+async fn transcribe_tiktok(
+    app: tauri::AppHandle,
+    settings: State<'_, SettingsStore>,
+    url: String,
+    language: Option<String>,
+    model: Option<String>,
+    word_timestamps: Option<bool>
+) -> Result<Transcription, AppError> {
     println!("Transcribing TikTok video: {}", url);
+    let settings = settings.get();
+    let language = language.unwrap_or_else(|| settings.whisper_language.clone());
+    let model_path = models::resolve(&app, model.as_deref(), &language)?;
     // TikTok goes straight to Whisper
-    transcribe_with_whisper(&url).await
+    let transcription = transcribe_with_whisper(&url, &settings, &language, &model_path, word_timestamps.unwrap_or(false)).await?;
+    transcript::save(&app, &transcription)?;
+    Ok(transcription)
 }
 
 // Transcribe any universal URL
 #[tauri::command]
-async fn transcribe_universal(url: String) -> Result<String, String> {
+async fn transcribe_universal(
+    app: tauri::AppHandle,
+    settings: State<'_, SettingsStore>,
+    url: String,
+    language: Option<String>,
+    model: Option<String>,
+    word_timestamps: Option<bool>
+) -> Result<Transcription, AppError> {
     println!("Transcribing universal URL: {}", url);
+    let settings = settings.get();
+    let language = language.unwrap_or_else(|| settings.whisper_language.clone());
+    let model_path = models::resolve(&app, model.as_deref(), &language)?;
     // Universal URLs go straight to Whisper
-    transcribe_with_whisper(&url).await
+    let transcription = transcribe_with_whisper(&url, &settings, &language, &model_path, word_timestamps.unwrap_or(false)).await?;
+    transcript::save(&app, &transcription)?;
+    Ok(transcription)
 }
 
 // Transcribe any audio/video file
 #[tauri::command]
 #[allow(non_snake_case)]
-async fn transcribe_file(filePath: String) -> Result<String, String> {
+async fn transcribe_file(
+    app: tauri::AppHandle,
+    settings: State<'_, SettingsStore>,
+    filePath: String,
+    language: Option<String>,
+    model: Option<String>,
+    word_timestamps: Option<bool>
+) -> Result<Transcription, AppError> {
     println!("Transcribing file: {}", filePath);
+    let language = language.unwrap_or_else(|| settings.get().whisper_language);
+    whisper::validate_language(&language)?;
+    let model_path = models::resolve(&app, model.as_deref(), &language)?;
     
     let path = PathBuf::from(&filePath);
     if !path.exists() {
         println!("File not found at path: {:?}", path);
-        return Err(format!("File not found: {}", filePath));
+        return Err(AppError::not_found(format!("File not found: {}", filePath)));
     }
     
     println!("File exists at: {:?}", path);
     
-    // Get the path to the whisper.cpp binary and model
-    let (whisper_path, model_path) = get_whisper_path()?;
-    
     // Create temporary files
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
         .as_secs();
     let wav_file = format!("/tmp/whisper_audio_{}.wav", timestamp);
-    let output_file = format!("/tmp/whisper_file_output_{}", timestamp);
     
     // First convert the file to WAV using ffmpeg
     println!("Converting to WAV: {} -> {}", filePath, wav_file);
@@ -1150,84 +930,20 @@ async fn transcribe_file(filePath: String) -> Result<String, String> {
             "-y"
         ])
         .output()
-        .map_err(|e| format!("FFmpeg conversion failed: {}", e))?;
+        .map_err(|e| AppError::spawn("ffmpeg", e))?;
     
     if !ffmpeg_output.status.success() {
         let stderr = String::from_utf8_lossy(&ffmpeg_output.stderr);
-        return Err(format!("FFmpeg conversion failed: {}", stderr));
+        return Err(AppError::ffmpeg(&stderr));
     }
     
     println!("Conversion successful, running whisper on WAV file");
-    
-    // Use whisper.cpp to transcribe the WAV file
-    println!("Running whisper with output file: {}", output_file);
-    let output = Command::new(&whisper_path)
-        .args(&[
-            "-m", model_path.to_str().ok_or("Invalid model path")?,
-            "-f", &wav_file,
-            "-otxt",
-            "-of", &output_file,
-            "--no-timestamps",
-            "-l", "en"
-        ])
-        .output()
-        .map_err(|e| format!("whisper.cpp failed: {}", e))?;
-    
-    println!("Whisper exit status: {}", output.status);
-    if !output.stderr.is_empty() {
-        println!("Whisper stderr: {}", String::from_utf8_lossy(&output.stderr));
-    }
-    if !output.stdout.is_empty() {
-        println!("Whisper stdout: {}", String::from_utf8_lossy(&output.stdout));
-    }
-    
-    if output.status.success() {
-        // Read the transcript file
-        let transcript_path = format!("{}.txt", output_file);
-        println!("Looking for transcript at: {}", transcript_path);
-        
-        // Check if file exists
-        if !PathBuf::from(&transcript_path).exists() {
-            // Try without .txt extension
-            println!("File not found at {}, trying without .txt", transcript_path);
-            if PathBuf::from(&output_file).exists() {
-                println!("Found file at {}", output_file);
-                let transcript = fs::read_to_string(&output_file)
-                    .map_err(|e| format!("Failed to read transcript: {}", e))?;
-                let _ = fs::remove_file(&output_file);
-                return Ok(transcript.trim().to_string());
-            }
-            
-            // List files in /tmp to debug
-            println!("Files in /tmp matching pattern:");
-            if let Ok(entries) = fs::read_dir("/tmp") {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        if path.to_string_lossy().contains("whisper_file_output") {
-                            println!("  Found: {:?}", path);
-                        }
-                    }
-                }
-            }
-            
-            return Err(format!("Transcript file not found at {} or {}", transcript_path, output_file));
-        }
-        
-        let transcript = fs::read_to_string(&transcript_path)
-            .map_err(|e| format!("Failed to read transcript: {}", e))?;
-        
-        // Clean up
-        let _ = fs::remove_file(&transcript_path);
-        let _ = fs::remove_file(&wav_file);
-        
-        Ok(transcript.trim().to_string())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        // Clean up WAV file even on error
-        let _ = fs::remove_file(&wav_file);
-        Err(format!("whisper.cpp transcription failed: {}", stderr))
-    }
+    let result = whisper::run(&wav_file, &language, &model_path, word_timestamps.unwrap_or(false));
+    // Clean up WAV file even on error
+    let _ = fs::remove_file(&wav_file);
+    let transcription = result?;
+    transcript::save(&app, &transcription)?;
+    Ok(transcription)
 }
 
 
@@ -1236,18 +952,18 @@ async fn transcribe_file(filePath: String) -> Result<String, String> {
 
 
 #[tauri::command]
-async fn show_main_window(app: tauri::AppHandle) -> Result<(), String> {
+async fn show_main_window(app: tauri::AppHandle) -> Result<(), AppError> {
     if let Some(window) = app.get_webview_window("main") {
         window.show().map_err(|e| e.to_string())?;
         window.set_focus().map_err(|e| e.to_string())?;
         Ok(())
     } else {
-        Err("Main window not found".to_string())
+        Err(AppError::not_found("Main window not found"))
     }
 }
 
 #[tauri::command]
-async fn quit_app(app: tauri::AppHandle) -> Result<(), String> {
+async fn quit_app(app: tauri::AppHandle) -> Result<(), AppError> {
     app.exit(0);
     Ok(())
 }
@@ -1259,6 +975,7 @@ async fn quit_app(app: tauri::AppHandle) -> Result<(), String> {
 pub fn run() {
 
     tauri::Builder::default()
+        .manage(DownloadRegistry::default())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
@@ -1266,7 +983,7 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             get_youtube_info,
             get_youtube_formats,
-            get_playlist_info,
+            playlist::get_playlist_info,
             is_playlist,
             download_youtube,
             download_universal,
@@ -1274,12 +991,53 @@ pub fn run() {
             transcribe_tiktok,
             transcribe_universal,
             transcribe_file,
+            downloads::cancel_download,
+            downloads::pause_download,
+            downloads::resume_download,
+            downloads::list_downloads,
+            downloads::get_download,
+            queue::get_queue,
+            queue::set_max_concurrent,
+            queue::set_download_priority,
+            history::list_history,
+            history::search_history,
+            history::delete_history,
+            history::redownload_history,
+            archive::check_already_downloaded,
+            settings::get_settings,
+            settings::update_settings,
+            sites::list_site_profiles,
+            sites::detect_site,
+            urls::classify_url,
+            subscriptions::list_subscriptions,
+            subscriptions::add_subscription,
+            subscriptions::update_subscription,
+            subscriptions::remove_subscription,
+            subscriptions::sync_subscription,
+            subscriptions::sync_all_subscriptions,
+            feeds::get_feed,
+            feeds::download_feed_episodes,
+            embed::embed_metadata,
+            models::list_whisper_models,
+            models::import_whisper_model,
+            models::verify_whisper_model,
+            models::delete_whisper_model,
+            models::set_default_whisper_model,
+            transcript::export_transcript,
             show_main_window,
             quit_app,
         ])
-        .setup(|_app| {
+        .setup(|app| {
             println!("Grably Desktop initialized - using bundled yt-dlp binary");
             
+            app.manage(SettingsStore::load(app.handle()));
+            
+            // Pick up downloads that were queued or running when the app closed
+            queue::restore(app.handle());
+            
+            // Sync subscriptions in the background when they are due
+            subscriptions::start_scheduler(app.handle().clone());
+            
             // Pre-warm the binaries on app startup to avoid first-run delays
             std::thread::spawn(|| {
                 println!("Pre-warming yt-dlp binary...");
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::{AppError, ErrorCode};
+use crate::settings::SettingsStore;
+use crate::whisper;
+
+// SHA-1 of the official whisper.cpp models, as published with them
+const BUILTIN_MANIFEST: &str = include_str!("../whisper-models.json");
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WhisperModel {
+    // File name without "ggml-" and ".bin", e.g. "large-v3" or "base.en"
+    pub id: String,
+    pub file_name: String,
+    pub path: String,
+    pub size: u64,
+    // English-only models are the ".en" ones
+    pub multilingual: bool,
+    // e.g. "q5_0", None for full precision
+    pub quantization: Option<String>,
+    // Shipped with the app, can't be deleted
+    pub bundled: bool,
+    pub is_default: bool,
+    // From the checksum manifest, None when the model isn't listed
+    pub expected_sha1: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelVerification {
+    pub id: String,
+    pub expected_sha1: Option<String>,
+    pub actual_sha1: String,
+    // None when the manifest doesn't know the model
+    pub valid: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct Manifest {
+    // File name to SHA-1
+    models: HashMap<String, String>,
+}
+
+fn models_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?
+        .join("models");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// Built-in checksums with app_config_dir/whisper-models.json on top, for
+// models we don't know about
+fn load_manifest(app: &AppHandle) -> HashMap<String, String> {
+    let mut models = serde_json::from_str::<Manifest>(BUILTIN_MANIFEST)
+        .unwrap_or_default()
+        .models;
+    let user_manifest = app.path().app_config_dir()
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join("whisper-models.json")).ok());
+    if let Some(json) = user_manifest {
+        match serde_json::from_str::<Manifest>(&json) {
+            Ok(manifest) => models.extend(manifest.models),
+            Err(e) => println!("Ignoring unreadable model manifest: {}", e),
+        }
+    }
+    models
+}
+
+fn model_id(file_name: &str) -> Option<String> {
+    let stem = file_name.strip_suffix(".bin")?;
+    let id = stem.strip_prefix("ggml-").unwrap_or(stem);
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+// "large-v3-q5_0" is quantized as q5_0
+fn quantization(id: &str) -> Option<String> {
+    let (_, last) = id.rsplit_once('-')?;
+    let is_quant = last.starts_with('q') && last[1..].starts_with(|c: char| c.is_ascii_digit());
+    is_quant.then(|| last.to_string())
+}
+
+fn scan(dir: &Path, bundled: bool, manifest: &HashMap<String, String>) -> Vec<WhisperModel> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut models: Vec<WhisperModel> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let id = model_id(&file_name)?;
+            let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+            Some(WhisperModel {
+                multilingual: !id.contains(".en"),
+                quantization: quantization(&id),
+                expected_sha1: manifest.get(&file_name).cloned(),
+                path: entry.path().to_string_lossy().to_string(),
+                size: metadata.len(),
+                bundled,
+                is_default: false,
+                file_name,
+                id,
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models
+}
+
+// Installed models first, then the ones bundled with the app
+pub fn list(app: &AppHandle) -> Result<Vec<WhisperModel>, AppError> {
+    let manifest = load_manifest(app);
+    let mut models = scan(&models_dir(app)?, false, &manifest);
+    for dir in whisper::resource_dirs() {
+        for model in scan(&dir, true, &manifest) {
+            if models.iter().all(|existing| existing.id != model.id) {
+                models.push(model);
+            }
+        }
+    }
+    let default = app.state::<SettingsStore>().get().whisper_model;
+    for model in &mut models {
+        model.is_default = default.as_deref() == Some(model.id.as_str());
+    }
+    Ok(models)
+}
+
+fn find(app: &AppHandle, id: &str) -> Result<WhisperModel, AppError> {
+    list(app)?
+        .into_iter()
+        .find(|model| model.id == id)
+        .ok_or_else(|| AppError::new(ErrorCode::ModelMissing, format!("Whisper model not installed: {}", id)))
+}
+
+// Model file for a job: the one asked for, else the default setting, else
+// base for the language
+pub fn resolve(app: &AppHandle, model: Option<&str>, language: &str) -> Result<PathBuf, AppError> {
+    let chosen = model.map(|id| id.to_string())
+        .or_else(|| app.state::<SettingsStore>().get().whisper_model);
+    if let Some(id) = chosen {
+        let model = find(app, &id)?;
+        if !model.multilingual && language != "en" {
+            return Err(AppError::invalid(format!(
+                "The {} model only transcribes English, pick a multilingual model", model.id
+            )));
+        }
+        return Ok(PathBuf::from(model.path));
+    }
+
+    let models = list(app)?;
+    let preferred: &[&str] = if language == "en" { &["base.en", "base"] } else { &["base"] };
+    preferred.iter()
+        .find_map(|id| models.iter().find(|model| model.id == *id))
+        .or_else(|| models.iter().find(|model| model.multilingual || language == "en"))
+        .map(|model| PathBuf::from(&model.path))
+        .ok_or_else(|| AppError::new(
+            ErrorCode::ModelMissing,
+            format!("No whisper model for language \"{}\" found, a multilingual model is needed", language),
+        ))
+}
+
+fn sha1_file(path: &Path) -> Result<String, AppError> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buffer = vec![0; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Installed and bundled whisper models
+#[tauri::command]
+pub async fn list_whisper_models(app: AppHandle) -> Result<Vec<WhisperModel>, AppError> {
+    list(&app)
+}
+
+// Copy a ggml model file into the models folder. Models the manifest
+// knows must match their checksum.
+#[tauri::command]
+pub async fn import_whisper_model(app: AppHandle, path: String) -> Result<WhisperModel, AppError> {
+    println!("Importing whisper model: {}", path);
+    let source = PathBuf::from(&path);
+    if !source.is_file() {
+        return Err(AppError::not_found(format!("File not found: {}", path)));
+    }
+    let file_name = source.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let Some(id) = model_id(&file_name) else {
+        return Err(AppError::invalid("Whisper models are ggml .bin files"));
+    };
+    if list(&app)?.iter().any(|model| model.id == id && !model.bundled) {
+        return Err(AppError::invalid(format!("Model {} is already installed", id)));
+    }
+
+    if let Some(expected) = load_manifest(&app).get(&file_name) {
+        let actual = sha1_file(&source)?;
+        if &actual != expected {
+            return Err(AppError::invalid(format!(
+                "Checksum mismatch for {}, the file is damaged or not the official model", file_name
+            )));
+        }
+    }
+
+    // Copy next to the destination first so a cancelled import never
+    // leaves a truncated model behind
+    let dir = models_dir(&app)?;
+    let temp = dir.join(format!("{}.part", file_name));
+    fs::copy(&source, &temp)?;
+    fs::rename(&temp, dir.join(&file_name))?;
+    find(&app, &id)
+}
+
+// Compare a model with its checksum in the manifest
+#[tauri::command]
+pub async fn verify_whisper_model(app: AppHandle, id: String) -> Result<ModelVerification, AppError> {
+    let model = find(&app, &id)?;
+    let actual_sha1 = sha1_file(Path::new(&model.path))?;
+    Ok(ModelVerification {
+        valid: model.expected_sha1.as_ref().map(|expected| *expected == actual_sha1),
+        id: model.id,
+        expected_sha1: model.expected_sha1,
+        actual_sha1,
+    })
+}
+
+#[tauri::command]
+pub async fn delete_whisper_model(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    id: String,
+) -> Result<(), AppError> {
+    let model = find(&app, &id)?;
+    if model.bundled {
+        return Err(AppError::invalid("Models bundled with the app can't be deleted"));
+    }
+    fs::remove_file(&model.path)?;
+    if model.is_default {
+        settings.update(&app, |settings| settings.whisper_model = None)?;
+    }
+    Ok(())
+}
+
+// Model used when a transcription doesn't pick one, None picks by language
+#[tauri::command]
+pub async fn set_default_whisper_model(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    id: Option<String>,
+) -> Result<(), AppError> {
+    if let Some(id) = &id {
+        find(&app, id)?;
+    }
+    settings.update(&app, |settings| settings.whisper_model = id)?;
+    Ok(())
+}
@@ -0,0 +1,241 @@
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::{classify_ytdlp_error, AppError};
+use crate::get_ytdlp_path;
+
+// Entries fetched per page when the caller doesn't say
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlaylistVideo {
+    // 1-based position in the playlist
+    pub index: Option<usize>,
+    pub id: String,
+    pub title: String,
+    pub duration: Option<f32>,
+    pub thumbnail: Option<String>,
+    pub url: Option<String>,
+    // YYYYMMDD, only some sites include it in flat listings
+    pub upload_date: Option<String>,
+    // yt-dlp extractor, e.g. "Youtube", used for download archive lookups
+    pub extractor_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlaylistInfo {
+    pub title: String,
+    pub uploader: Option<String>,
+    pub video_count: usize,
+    pub videos: Vec<PlaylistVideo>,
+    pub thumbnail: Option<String>,
+    // Offset of the first entry in videos
+    pub offset: usize,
+    // More entries can be fetched with offset + videos.len()
+    pub has_more: bool,
+}
+
+fn parse_entry(entry: &serde_json::Value) -> Option<PlaylistVideo> {
+    Some(PlaylistVideo {
+        index: entry["playlist_index"].as_u64().map(|index| index as usize),
+        id: entry["id"].as_str()?.to_string(),
+        title: entry["title"].as_str().unwrap_or("Unknown").to_string(),
+        duration: entry["duration"].as_f64().map(|d| d as f32),
+        thumbnail: entry["thumbnail"].as_str()
+            .or_else(|| entry["thumbnails"].as_array()?.last()?["url"].as_str())
+            .map(|s| s.to_string()),
+        url: entry["url"].as_str().map(|s| s.to_string())
+            .or_else(|| entry["id"].as_str().map(|id| format!("https://youtube.com/watch?v={}", id))),
+        upload_date: entry["upload_date"].as_str().map(|s| s.to_string()),
+        extractor_key: entry["ie_key"].as_str()
+            .or_else(|| entry["extractor_key"].as_str())
+            .map(|s| s.to_string()),
+    })
+}
+
+// List limit entries of a playlist starting at offset with a flat
+// listing, calling on_entry for each one as yt-dlp prints it
+pub fn fetch_playlist(
+    url: &str,
+    offset: usize,
+    limit: usize,
+    mut on_entry: impl FnMut(&PlaylistVideo),
+) -> Result<PlaylistInfo, AppError> {
+    let limit = limit.max(1);
+    println!("Getting playlist info for URL: {} (offset {}, limit {})", url, offset, limit);
+
+    // --playlist-items is 1-based and inclusive
+    let items = format!("{}:{}", offset + 1, offset + limit);
+    let mut child = Command::new(get_ytdlp_path())
+        .args([
+            "--flat-playlist",
+            "-j",
+            "--no-warnings",
+            "--playlist-items", &items,
+            url,
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::spawn("yt-dlp", e))?;
+
+    // Drain stderr on its own thread so yt-dlp never blocks on a full pipe
+    let stderr_reader = child.stderr.take().map(|stderr| {
+        std::thread::spawn(move || {
+            let mut log = String::new();
+            BufReader::new(stderr).read_to_string(&mut log).ok();
+            log
+        })
+    });
+
+    let mut first_entry: Option<serde_json::Value> = None;
+    let mut videos = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let Ok(entry) = serde_json::from_str::<serde_json::Value>(&line) else {
+                continue;
+            };
+            if let Some(video) = parse_entry(&entry) {
+                on_entry(&video);
+                videos.push(video);
+            }
+            if first_entry.is_none() {
+                first_entry = Some(entry);
+            }
+        }
+    }
+
+    let status = child.wait()?;
+    let stderr = stderr_reader
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default();
+    // Unavailable entries make yt-dlp exit non-zero, only fail if nothing came back
+    if !status.success() && videos.is_empty() {
+        return Err(classify_ytdlp_error(&stderr));
+    }
+
+    let first = first_entry.unwrap_or_default();
+    // A plain video URL lists itself without any playlist fields
+    if first["playlist_id"].is_null() && first["playlist"].is_null() {
+        return Ok(PlaylistInfo {
+            title: first["title"].as_str().unwrap_or("Unknown Playlist").to_string(),
+            uploader: first["uploader"].as_str().map(|s| s.to_string()),
+            video_count: 0,
+            videos: Vec::new(),
+            thumbnail: None,
+            offset,
+            has_more: false,
+        });
+    }
+
+    let video_count = first["playlist_count"].as_u64()
+        .map(|count| count as usize)
+        .unwrap_or(offset + videos.len());
+    let has_more = if first["playlist_count"].is_u64() {
+        offset + videos.len() < video_count
+    } else {
+        videos.len() == limit
+    };
+
+    Ok(PlaylistInfo {
+        title: first["playlist_title"].as_str()
+            .or_else(|| first["playlist"].as_str())
+            .unwrap_or("Unknown Playlist")
+            .to_string(),
+        uploader: first["playlist_uploader"].as_str()
+            .or_else(|| first["playlist_channel"].as_str())
+            .map(|s| s.to_string()),
+        video_count,
+        thumbnail: videos.first().and_then(|video| video.thumbnail.clone()),
+        videos,
+        offset,
+        has_more,
+    })
+}
+
+// Get one page of a playlist. Entries are also emitted as "playlist-entry"
+// events while yt-dlp lists them, so long lists can render progressively.
+#[tauri::command]
+pub async fn get_playlist_info(
+    app: AppHandle,
+    url: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<PlaylistInfo, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    fetch_playlist(&url, offset.unwrap_or(0), limit, |video| {
+        app.emit("playlist-entry", serde_json::json!({
+            "url": url,
+            "entry": video
+        })).ok();
+    })
+}
+
+// Inclusive 1-based index range, no end means up to the last entry
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndexRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+// Which entries of a playlist to download
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PlaylistSelection {
+    // 1-based playlist indices
+    pub items: Vec<usize>,
+    pub ranges: Vec<IndexRange>,
+    // Upload date bounds, inclusive, as YYYYMMDD or yt-dlp's "now-1week" form
+    pub date_after: Option<String>,
+    pub date_before: Option<String>,
+}
+
+pub fn is_valid_date(date: &str) -> bool {
+    (date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()))
+        || ["now", "today", "yesterday"].iter().any(|prefix| date.starts_with(prefix))
+}
+
+impl PlaylistSelection {
+    // yt-dlp arguments for this selection
+    pub fn args(&self) -> Result<Vec<String>, AppError> {
+        let mut specs: Vec<String> = Vec::new();
+        for &item in &self.items {
+            if item == 0 {
+                return Err(AppError::invalid("Playlist items start at 1"));
+            }
+            specs.push(item.to_string());
+        }
+        for range in &self.ranges {
+            match range.end {
+                _ if range.start == 0 => {
+                    return Err(AppError::invalid("Playlist ranges start at 1"));
+                }
+                Some(end) if end < range.start => {
+                    return Err(AppError::invalid(format!(
+                        "Invalid playlist range {}-{}", range.start, end
+                    )));
+                }
+                Some(end) => specs.push(format!("{}:{}", range.start, end)),
+                None => specs.push(format!("{}:", range.start)),
+            }
+        }
+
+        let mut args = Vec::new();
+        if !specs.is_empty() {
+            args.push("--playlist-items".to_string());
+            args.push(specs.join(","));
+        }
+        for (flag, date) in [("--dateafter", &self.date_after), ("--datebefore", &self.date_before)] {
+            if let Some(date) = date {
+                if !is_valid_date(date) {
+                    return Err(AppError::invalid(format!("Invalid date: {}", date)));
+                }
+                args.push(flag.to_string());
+                args.push(date.clone());
+            }
+        }
+        Ok(args)
+    }
+}
@@ -0,0 +1,191 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::error::AppError;
+use crate::downloads::{launch, DownloadInfo, DownloadJob, DownloadRegistry, DownloadState};
+
+pub const DEFAULT_MAX_CONCURRENT: usize = 3;
+
+// Serialises writes to queue.json, schedule() runs on several threads
+static PERSIST_LOCK: Mutex<()> = Mutex::new(());
+
+// On-disk format of the queue
+#[derive(Debug, Serialize, Deserialize)]
+struct QueueFile {
+    max_concurrent: usize,
+    jobs: Vec<DownloadJob>,
+}
+
+// Queue state as sent with the "queue-changed" event
+#[derive(Debug, Serialize, Clone)]
+pub struct QueueSnapshot {
+    max_concurrent: usize,
+    running: usize,
+    // Queued downloads in the order they will start
+    queued: Vec<DownloadInfo>,
+}
+
+fn snapshot(registry: &DownloadRegistry) -> QueueSnapshot {
+    let downloads = registry.list();
+    let running = downloads.iter()
+        .filter(|info| info.state.is_running())
+        .count();
+    let queued = registry.queued_ids()
+        .iter()
+        .filter_map(|id| registry.get(id))
+        .collect();
+    QueueSnapshot {
+        max_concurrent: registry.max_concurrent(),
+        running,
+        queued,
+    }
+}
+
+fn queue_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    Ok(dir.join("queue.json"))
+}
+
+// Write every unfinished job to disk so it survives a restart
+fn persist(app: &AppHandle) -> Result<(), String> {
+    let registry = app.state::<DownloadRegistry>();
+    let file = QueueFile {
+        max_concurrent: registry.max_concurrent(),
+        jobs: registry.pending_jobs(),
+    };
+    let json = serde_json::to_string_pretty(&file)
+        .map_err(|e| format!("Failed to serialize queue: {}", e))?;
+
+    let path = queue_file_path(app)?;
+    let _guard = PERSIST_LOCK.lock().unwrap();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    // Write then rename so a crash never leaves a truncated queue behind
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)
+        .map_err(|e| format!("Failed to write queue: {}", e))?;
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| format!("Failed to write queue: {}", e))?;
+    Ok(())
+}
+
+// Start as many queued downloads as the concurrency limit allows, then
+// persist the queue and tell the frontend about the new state
+pub fn schedule(app: &AppHandle) {
+    let registry = app.state::<DownloadRegistry>();
+    loop {
+        let claimed = registry.claim_next();
+        if claimed.is_empty() {
+            break;
+        }
+        let mut failed = false;
+        for id in claimed {
+            println!("Starting queued download: {}", id);
+            if let Err(e) = launch(app, &id) {
+                // The slot is free again, so go round for the next job
+                failed = true;
+                registry.update(&id, |info| {
+                    info.state = DownloadState::Failed;
+                    info.error = Some(e.clone());
+                });
+                let filename = registry.get(&id).map(|info| info.filename).unwrap_or_default();
+                app.emit("download-status", serde_json::json!({
+                    "id": id,
+                    "filename": filename,
+                    "status": format!("Error: {}", e),
+                    "percent": 0.0
+                })).ok();
+                app.emit("download-failed", serde_json::json!({
+                    "id": id,
+                    "filename": filename,
+                    "error": e
+                })).ok();
+            }
+        }
+        if !failed {
+            break;
+        }
+    }
+
+    if let Err(e) = persist(app) {
+        println!("Failed to persist download queue: {}", e);
+    }
+    app.emit("queue-changed", snapshot(&registry)).ok();
+}
+
+// Reload queued and interrupted downloads from the last session
+pub fn restore(app: &AppHandle) {
+    let path = match queue_file_path(app) {
+        Ok(path) => path,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let Ok(json) = fs::read_to_string(&path) else {
+        return;
+    };
+    let file: QueueFile = match serde_json::from_str(&json) {
+        Ok(file) => file,
+        Err(e) => {
+            println!("Ignoring unreadable queue file {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    let registry = app.state::<DownloadRegistry>();
+    registry.set_max_concurrent(file.max_concurrent);
+    println!("Restoring {} downloads from the last session", file.jobs.len());
+    for mut job in file.jobs {
+        job.info.pid = None;
+        // Paused jobs stay paused, anything that was running is re-queued
+        if job.info.state != DownloadState::Paused {
+            job.info.state = DownloadState::Queued;
+        }
+        registry.insert(job);
+    }
+    schedule(app);
+}
+
+// Current queue state
+#[tauri::command]
+pub async fn get_queue(registry: State<'_, DownloadRegistry>) -> Result<QueueSnapshot, AppError> {
+    Ok(snapshot(&registry))
+}
+
+// Change how many downloads may run at the same time
+#[tauri::command]
+pub async fn set_max_concurrent(
+    app: AppHandle,
+    registry: State<'_, DownloadRegistry>,
+    max: usize,
+) -> Result<(), AppError> {
+    println!("Setting max concurrent downloads to {}", max);
+    registry.set_max_concurrent(max);
+    schedule(&app);
+    Ok(())
+}
+
+// Move a queued download up (higher priority) or down the queue
+#[tauri::command]
+pub async fn set_download_priority(
+    app: AppHandle,
+    registry: State<'_, DownloadRegistry>,
+    id: String,
+    priority: i32,
+) -> Result<(), AppError> {
+    let info = registry.get(&id)
+        .ok_or_else(|| AppError::not_found(format!("Download not found: {}", id)))?;
+    if info.state.is_terminal() {
+        return Err(AppError::invalid(format!("Download already finished: {}", id)));
+    }
+    registry.update(&id, |info| info.priority = priority);
+    schedule(&app);
+    Ok(())
+}
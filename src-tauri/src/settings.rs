@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::embed::EmbedOptions;
+use crate::error::AppError;
+
+// Bump when the on-disk layout changes and add a step to migrate()
+const SETTINGS_VERSION: u32 = 1;
+
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+// User preferences, persisted as settings.json in the app config dir
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    // Where downloads go, defaults to ~/Downloads/Grably
+    pub output_dir: Option<String>,
+    // Netscape cookies file handed to yt-dlp when it exists
+    pub cookies_file: Option<String>,
+    pub user_agent: String,
+    // yt-dlp --audio-quality for audio downloads (0 = best)
+    pub audio_quality: String,
+    // yt-dlp --audio-quality for audio fetched only for transcription
+    pub transcription_audio_quality: String,
+    pub whisper_language: String,
+    // Whisper model id used when a job doesn't pick one, chosen by
+    // language when unset
+    pub whisper_model: Option<String>,
+    pub subtitle_language: String,
+    // File name of split chapters, with {index}, {title} and {video}
+    pub chapter_template: String,
+    // Tags, cover art and chapters written into downloads, all off by default
+    pub embed: EmbedOptions,
+    // Use the library's download archive unless a command says otherwise
+    pub use_archive: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            output_dir: None,
+            cookies_file: Some("/tmp/cookies.txt".to_string()),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            audio_quality: "0".to_string(),
+            transcription_audio_quality: "5".to_string(),
+            whisper_language: "en".to_string(),
+            whisper_model: None,
+            subtitle_language: "en".to_string(),
+            chapter_template: "{index} - {title}".to_string(),
+            embed: EmbedOptions::default(),
+            use_archive: false,
+        }
+    }
+}
+
+impl Settings {
+    // Output folder, created on first use
+    pub fn output_dir(&self) -> PathBuf {
+        let dir = self.output_dir.as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                dirs::download_dir()
+                    .unwrap_or_else(|| PathBuf::from("/tmp"))
+                    .join("Grably")
+            });
+        if !dir.exists() {
+            fs::create_dir_all(&dir).ok();
+        }
+        dir
+    }
+
+    // Cookies file, only if it is configured and actually exists
+    pub fn cookies_file(&self) -> Option<String> {
+        self.cookies_file.clone()
+            .filter(|path| PathBuf::from(path).exists())
+    }
+}
+
+// Bring an older settings document up to SETTINGS_VERSION. The layout
+// hasn't changed yet, so for now older files only get the version stamped.
+fn migrate(mut value: serde_json::Value) -> serde_json::Value {
+    value["version"] = serde_json::json!(SETTINGS_VERSION);
+    value
+}
+
+pub struct SettingsStore {
+    settings: Mutex<Settings>,
+    path: Option<PathBuf>,
+}
+
+impl SettingsStore {
+    pub fn load(app: &AppHandle) -> Self {
+        let path = app.path().app_config_dir()
+            .map(|dir| dir.join("settings.json"))
+            .ok();
+        let settings = path.as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+            .map(migrate)
+            .and_then(|value| match serde_json::from_value(value) {
+                Ok(settings) => Some(settings),
+                Err(e) => {
+                    println!("Ignoring unreadable settings: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        SettingsStore {
+            settings: Mutex::new(settings),
+            path,
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    // Change, persist and announce the settings
+    pub fn update<F: FnOnce(&mut Settings)>(&self, app: &AppHandle, f: F) -> Result<Settings, AppError> {
+        let mut updated = self.get();
+        f(&mut updated);
+        self.save(&updated)?;
+        *self.settings.lock().unwrap() = updated.clone();
+        app.emit("settings-changed", &updated).ok();
+        Ok(updated)
+    }
+
+    fn save(&self, settings: &Settings) -> Result<(), AppError> {
+        let Some(path) = &self.path else {
+            return Err("App config dir is not available".into());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+// Current settings
+#[tauri::command]
+pub async fn get_settings(store: State<'_, SettingsStore>) -> Result<Settings, AppError> {
+    Ok(store.get())
+}
+
+// Update some or all settings. Fields left out keep their current value.
+#[tauri::command]
+pub async fn update_settings(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    settings: serde_json::Value,
+) -> Result<Settings, AppError> {
+    let serde_json::Value::Object(changes) = settings else {
+        return Err(AppError::invalid("Settings must be an object"));
+    };
+
+    let mut merged = serde_json::to_value(store.get())
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    for (key, value) in changes {
+        merged[key] = value;
+    }
+    merged["version"] = serde_json::json!(SETTINGS_VERSION);
+    let updated: Settings = serde_json::from_value(merged)
+        .map_err(|e| AppError::invalid(format!("Invalid settings: {}", e)))?;
+
+    store.update(&app, |settings| *settings = updated)
+}
@@ -0,0 +1,200 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::error::AppError;
+use crate::settings::Settings;
+
+// Profiles shipped with the app, user overrides are merged on top
+const BUILTIN_PROFILES: &str = include_str!("../site-profiles.json");
+
+// Where yt-dlp gets cookies from for a site
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CookieSource {
+    // The cookies file configured in settings, if it exists
+    Settings,
+    File { path: String },
+    // Passed to --cookies-from-browser, e.g. "firefox" or "chrome:Profile 1"
+    Browser { browser: String },
+    None,
+}
+
+// How to download from one site
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SiteProfile {
+    pub id: String,
+    // Other ids a site_type may use for the profile, e.g. "x" for twitter
+    pub aliases: Vec<String>,
+    pub name: String,
+    // "host" or "host/path-prefix", subdomains of the host match as well
+    pub url_patterns: Vec<String>,
+    // Passed as --add-header "Name:Value"
+    pub headers: Vec<String>,
+    // Passed as --extractor-args
+    pub extractor_args: Vec<String>,
+    // yt-dlp output template, {timestamp} keeps simultaneous downloads apart
+    pub output_template: String,
+    pub cookies: CookieSource,
+}
+
+impl Default for SiteProfile {
+    fn default() -> Self {
+        SiteProfile {
+            id: String::new(),
+            aliases: Vec::new(),
+            name: String::new(),
+            url_patterns: Vec::new(),
+            headers: Vec::new(),
+            extractor_args: Vec::new(),
+            output_template: "%(title)s_{timestamp}.%(ext)s".to_string(),
+            cookies: CookieSource::Settings,
+        }
+    }
+}
+
+impl SiteProfile {
+    // Used when no profile matches the URL
+    pub fn generic() -> Self {
+        SiteProfile {
+            id: "generic".to_string(),
+            name: "Media".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_lowercase();
+        self.url_patterns.iter().any(|pattern| {
+            let (pattern_host, path_prefix) = match pattern.split_once('/') {
+                Some((pattern_host, path)) => (pattern_host, Some(format!("/{}", path))),
+                None => (pattern.as_str(), None),
+            };
+            let pattern_host = pattern_host.to_lowercase();
+            let host_matches = host == pattern_host || host.ends_with(&format!(".{}", pattern_host));
+            host_matches && path_prefix.is_none_or(|prefix| url.path().starts_with(&prefix))
+        })
+    }
+
+    // yt-dlp arguments for headers, extractor args and cookies
+    pub fn args(&self, settings: &Settings) -> Vec<String> {
+        let mut args = Vec::new();
+        for header in &self.headers {
+            args.push("--add-header".to_string());
+            args.push(header.clone());
+        }
+        for extractor_args in &self.extractor_args {
+            args.push("--extractor-args".to_string());
+            args.push(extractor_args.clone());
+        }
+        match &self.cookies {
+            CookieSource::Settings => {
+                if let Some(cookies) = settings.cookies_file() {
+                    args.push("--cookies".to_string());
+                    args.push(cookies);
+                }
+            }
+            CookieSource::File { path } => {
+                args.push("--cookies".to_string());
+                args.push(path.clone());
+            }
+            CookieSource::Browser { browser } => {
+                args.push("--cookies-from-browser".to_string());
+                args.push(browser.clone());
+            }
+            CookieSource::None => {}
+        }
+        args
+    }
+
+    pub fn output_template(&self, timestamp: u128) -> String {
+        self.output_template.replace("{timestamp}", &timestamp.to_string())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileFile {
+    profiles: Vec<SiteProfile>,
+}
+
+// User overrides live next to settings.json
+fn user_profiles_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir()
+        .map(|dir| dir.join("site-profiles.json"))
+        .ok()
+}
+
+fn parse_profiles(json: &str) -> Result<Vec<SiteProfile>, serde_json::Error> {
+    serde_json::from_str::<ProfileFile>(json).map(|file| file.profiles)
+}
+
+// Built-in profiles with the user's file merged on top. A user profile
+// replaces the built-in one with the same id, new ones are tried first.
+// The file is read on every call so edits apply without a restart.
+pub fn load_profiles(app: &AppHandle) -> Vec<SiteProfile> {
+    let mut profiles = parse_profiles(BUILTIN_PROFILES).unwrap_or_else(|e| {
+        println!("Built-in site profiles are invalid: {}", e);
+        Vec::new()
+    });
+
+    let Some(path) = user_profiles_path(app) else {
+        return profiles;
+    };
+    let Ok(json) = fs::read_to_string(&path) else {
+        return profiles;
+    };
+    let overrides = match parse_profiles(&json) {
+        Ok(overrides) => overrides,
+        Err(e) => {
+            println!("Ignoring unreadable site profiles {:?}: {}", path, e);
+            return profiles;
+        }
+    };
+
+    let mut added = Vec::new();
+    for profile in overrides {
+        match profiles.iter_mut().find(|existing| existing.id == profile.id) {
+            Some(existing) => *existing = profile,
+            None => added.push(profile),
+        }
+    }
+    added.extend(profiles);
+    added
+}
+
+// First profile whose patterns match the URL
+pub fn detect<'a>(profiles: &'a [SiteProfile], url: &str) -> Option<&'a SiteProfile> {
+    let url = Url::parse(url).ok()?;
+    profiles.iter().find(|profile| profile.matches(&url))
+}
+
+// Profile for an explicit site_type, by id or alias. "generic" is the
+// fallback profile.
+pub fn find(profiles: &[SiteProfile], site_type: &str) -> Result<SiteProfile, AppError> {
+    if site_type == "generic" {
+        return Ok(SiteProfile::generic());
+    }
+    profiles.iter()
+        .find(|profile| profile.id == site_type || profile.aliases.iter().any(|alias| alias == site_type))
+        .cloned()
+        .ok_or_else(|| AppError::invalid(format!("Unknown site type: {}", site_type)))
+}
+
+// All site profiles in the order they are matched
+#[tauri::command]
+pub async fn list_site_profiles(app: AppHandle) -> Result<Vec<SiteProfile>, AppError> {
+    Ok(load_profiles(&app))
+}
+
+// Profile that would be used to download a URL, None for generic sites
+#[tauri::command]
+pub async fn detect_site(app: AppHandle, url: String) -> Result<Option<SiteProfile>, AppError> {
+    let profiles = load_profiles(&app);
+    Ok(detect(&profiles, &url).cloned())
+}
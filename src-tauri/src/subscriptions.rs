@@ -0,0 +1,473 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use url::Url;
+
+use crate::downloads::{enqueue_download, DownloadInfo, DownloadKind, DownloadRegistry};
+use crate::error::AppError;
+use crate::playlist::{fetch_playlist, is_valid_date, PlaylistVideo};
+use crate::settings::SettingsStore;
+use crate::urls::{self, UrlKind};
+use crate::{archive, format_args, get_ffmpeg_path};
+
+// How often the scheduler looks for subscriptions that are due
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+// Newest entries compared against the library on each sync
+const SYNC_WINDOW: usize = 50;
+
+// Serialises read-modify-write cycles on subscriptions.json
+static SUBSCRIPTIONS_LOCK: Mutex<()> = Mutex::new(());
+
+// Which new entries get downloaded
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SubscriptionFilters {
+    pub title_regex: Option<String>,
+    // Seconds
+    pub min_duration: Option<f32>,
+    pub max_duration: Option<f32>,
+    // YYYYMMDD, inclusive
+    pub date_after: Option<String>,
+}
+
+impl SubscriptionFilters {
+    fn title_regex(&self) -> Result<Option<Regex>, AppError> {
+        self.title_regex.as_deref()
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| Regex::new(pattern)
+                .map_err(|e| AppError::invalid(format!("Invalid title filter: {}", e))))
+            .transpose()
+    }
+
+    fn validate(&self) -> Result<(), AppError> {
+        self.title_regex()?;
+        if let Some(date) = &self.date_after {
+            if !is_valid_date(date) {
+                return Err(AppError::invalid(format!("Invalid date: {}", date)));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_duration, self.max_duration) {
+            if min > max {
+                return Err(AppError::invalid("Minimum duration is longer than the maximum"));
+            }
+        }
+        Ok(())
+    }
+
+    // Check what the flat listing tells us, unknown values pass here and
+    // are checked again by yt-dlp through args()
+    fn matches(&self, video: &PlaylistVideo, title_regex: Option<&Regex>) -> bool {
+        if title_regex.is_some_and(|regex| !regex.is_match(&video.title)) {
+            return false;
+        }
+        if let Some(duration) = video.duration {
+            if self.min_duration.is_some_and(|min| duration < min)
+                || self.max_duration.is_some_and(|max| duration > max)
+            {
+                return false;
+            }
+        }
+        if let (Some(after), Some(date)) = (&self.date_after, &video.upload_date) {
+            // Relative dates like "now-1week" are left to yt-dlp
+            if after.chars().all(|c| c.is_ascii_digit()) && date < after {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut conditions = Vec::new();
+        if let Some(min) = self.min_duration {
+            conditions.push(format!("duration >=? {}", min));
+        }
+        if let Some(max) = self.max_duration {
+            conditions.push(format!("duration <=? {}", max));
+        }
+        if !conditions.is_empty() {
+            args.push("--match-filter".to_string());
+            args.push(conditions.join(" & "));
+        }
+        if let Some(date) = &self.date_after {
+            args.push("--dateafter".to_string());
+            args.push(date.clone());
+        }
+        args
+    }
+}
+
+// A channel or playlist whose new entries are downloaded automatically
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Subscription {
+    pub id: String,
+    pub url: String,
+    // Playlist or channel name, filled in by the first sync
+    pub title: Option<String>,
+    // Format preset as passed to download_youtube, e.g. "mp3"
+    pub format: Option<String>,
+    // Target folder, defaults to the output folder from settings
+    pub output_dir: Option<String>,
+    #[serde(default)]
+    pub filters: SubscriptionFilters,
+    // Sync automatically this often, None means only on demand
+    pub interval_minutes: Option<u64>,
+    pub enabled: bool,
+    pub created_at: u64,
+    pub last_synced_at: Option<u64>,
+    // Last sync, failed or not. The scheduler counts the interval from it
+    // so a failing subscription isn't retried on every tick.
+    #[serde(default)]
+    pub last_attempt_at: Option<u64>,
+    pub last_error: Option<AppError>,
+    // Entries of the last sync window the filters turned down, as archive
+    // keys (or URLs), so they aren't queued or checked again
+    #[serde(default)]
+    pub seen: Vec<String>,
+}
+
+// Outcome of one sync, also sent as the "subscription-synced" event
+#[derive(Debug, Serialize, Clone)]
+pub struct SyncResult {
+    subscription_id: String,
+    // Entries that were checked
+    checked: usize,
+    // download_ids of the entries that were queued
+    queued: Vec<String>,
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn subscriptions_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    Ok(dir.join("subscriptions.json"))
+}
+
+// A missing file means no subscriptions yet. An unreadable one is an
+// error, writing back an empty list would delete every subscription.
+fn read_subscriptions(path: &PathBuf) -> Result<Vec<Subscription>, AppError> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::from(format!("Failed to read {}: {}", path.display(), e))),
+    };
+    serde_json::from_str(&json)
+        .map_err(|e| AppError::from(format!("Failed to parse {}: {}", path.display(), e)))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<Subscription>, AppError> {
+    let path = subscriptions_path(app)?;
+    let _guard = SUBSCRIPTIONS_LOCK.lock().unwrap();
+    read_subscriptions(&path)
+}
+
+// Load, change and write back the subscriptions under one lock
+fn modify<T>(
+    app: &AppHandle,
+    change: impl FnOnce(&mut Vec<Subscription>) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let path = subscriptions_path(app)?;
+    let _guard = SUBSCRIPTIONS_LOCK.lock().unwrap();
+    let mut subscriptions = read_subscriptions(&path)?;
+    let result = change(&mut subscriptions)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(&subscriptions)
+        .map_err(|e| format!("Failed to serialize subscriptions: {}", e))?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(result)
+}
+
+fn find_mut<'a>(subscriptions: &'a mut [Subscription], id: &str) -> Result<&'a mut Subscription, AppError> {
+    subscriptions.iter_mut()
+        .find(|subscription| subscription.id == id)
+        .ok_or_else(|| AppError::not_found(format!("Subscription not found: {}", id)))
+}
+
+// A YouTube channel root lists the channel's tabs, sync its uploads
+fn listing_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    let is_youtube = parsed.host_str()
+        .is_some_and(|host| host == "youtube.com" || host.ends_with(".youtube.com"));
+    let segments: Vec<String> = parsed.path().trim_matches('/').split('/').map(str::to_string).collect();
+    let is_root = match segments.as_slice() {
+        [handle] => handle.starts_with('@'),
+        [prefix, name] => matches!(prefix.as_str(), "channel" | "c" | "user") && !name.is_empty(),
+        _ => false,
+    };
+    if !is_youtube || !is_root {
+        return url.to_string();
+    }
+    parsed.set_path(&format!("/{}/videos", segments.join("/")));
+    parsed.to_string()
+}
+
+// Flat listings can hold channel tabs and nested playlists, which aren't
+// media to download
+fn is_listing_entry(video: &PlaylistVideo) -> bool {
+    video.extractor_key.as_deref()
+        .is_some_and(|key| key.ends_with("Tab") || key.contains("Playlist"))
+}
+
+// Key an entry is remembered by in Subscription::seen
+fn seen_key(video: &PlaylistVideo, url: &str) -> String {
+    video.extractor_key.as_deref()
+        .map(|key| archive::archive_key(key, &video.id))
+        .unwrap_or_else(|| url.to_string())
+}
+
+// Compare the newest entries with the library and queue what is missing.
+// The download archive in the target folder is what counts as downloaded,
+// entries that are queued or running right now are left alone.
+fn sync(app: &AppHandle, id: &str) -> Result<SyncResult, AppError> {
+    let subscription = load(app)?
+        .into_iter()
+        .find(|subscription| subscription.id == id)
+        .ok_or_else(|| AppError::not_found(format!("Subscription not found: {}", id)))?;
+    println!("Syncing subscription {} ({})", subscription.id, subscription.url);
+
+    let listing = match fetch_playlist(&listing_url(&subscription.url), 0, SYNC_WINDOW, |_| {}) {
+        Ok(listing) => listing,
+        Err(e) => {
+            modify(app, |subscriptions| {
+                let subscription = find_mut(subscriptions, id)?;
+                subscription.last_attempt_at = Some(now());
+                subscription.last_error = Some(e.clone());
+                Ok(())
+            })?;
+            return Err(e);
+        }
+    };
+
+    let settings = app.state::<SettingsStore>().get();
+    let library_dir = subscription.output_dir.as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| settings.output_dir());
+    fs::create_dir_all(&library_dir)?;
+    let title_regex = subscription.filters.title_regex()?;
+    let kind = match urls::classify(&subscription.url).ok().and_then(|c| c.platform) {
+        Some(platform) if platform == "youtube" => DownloadKind::Youtube,
+        _ => DownloadKind::Universal,
+    };
+    let downloads = app.state::<DownloadRegistry>().list();
+    let active: Vec<&String> = downloads.iter()
+        .filter(|info| !info.state.is_terminal())
+        .map(|info| &info.url)
+        .collect();
+    // Queued earlier but turned down by yt-dlp's own date or match filter
+    let rejected: Vec<&String> = downloads.iter()
+        .filter(|info| info.state.is_terminal() && info.filtered > 0)
+        .map(|info| &info.url)
+        .collect();
+
+    let mut queued = Vec::new();
+    let mut seen = Vec::new();
+    for video in &listing.videos {
+        let Some(url) = &video.url else {
+            continue;
+        };
+        if is_listing_entry(video) {
+            continue;
+        }
+        let key = seen_key(video, url);
+        if subscription.seen.contains(&key)
+            || rejected.contains(&url)
+            || !subscription.filters.matches(video, title_regex.as_ref())
+        {
+            seen.push(key);
+            continue;
+        }
+        if active.contains(&url) {
+            continue;
+        }
+        let archived = video.extractor_key.as_deref()
+            .is_some_and(|key| archive::is_archived(&library_dir, &archive::archive_key(key, &video.id)));
+        if archived {
+            continue;
+        }
+
+        let mut args = vec![];
+        let ffmpeg_path = get_ffmpeg_path();
+        if ffmpeg_path != "ffmpeg" {
+            args.push("--ffmpeg-location".to_string());
+            args.push(ffmpeg_path);
+        }
+        args.extend([
+            "--no-playlist".to_string(),
+            "--progress".to_string(),
+            "--newline".to_string(),
+            "-o".to_string(),
+            library_dir.join("%(title)s.%(ext)s").to_string_lossy().to_string(),
+        ]);
+        // Recording finished entries is what keeps the next sync from
+        // queuing them again
+        args.extend(archive::archive_args(&library_dir));
+        args.extend(subscription.filters.args());
+        args.extend(format_args(&settings, subscription.format.clone())?);
+        args.push(url.clone());
+
+        use uuid::Uuid;
+        let download_id = Uuid::new_v4().to_string();
+        let info = DownloadInfo::new(&download_id, url, &video.title, &library_dir, kind);
+        enqueue_download(app, info, args, None);
+        queued.push(download_id);
+    }
+
+    modify(app, |subscriptions| {
+        let subscription = find_mut(subscriptions, id)?;
+        subscription.title = Some(listing.title.clone());
+        subscription.last_synced_at = Some(now());
+        subscription.last_attempt_at = subscription.last_synced_at;
+        subscription.last_error = None;
+        // Entries that left the window are never listed again
+        subscription.seen = seen;
+        Ok(())
+    })?;
+
+    let result = SyncResult {
+        subscription_id: id.to_string(),
+        checked: listing.videos.len(),
+        queued,
+    };
+    println!("Subscription {} queued {} new items", id, result.queued.len());
+    app.emit("subscription-synced", &result).ok();
+    Ok(result)
+}
+
+// Background thread that syncs subscriptions when their interval is up
+pub fn start_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SCHEDULER_TICK);
+        let Ok(subscriptions) = load(&app) else {
+            continue;
+        };
+        for subscription in subscriptions {
+            let Some(interval) = subscription.interval_minutes.filter(|_| subscription.enabled) else {
+                continue;
+            };
+            let due = subscription.last_attempt_at
+                .or(subscription.last_synced_at)
+                .is_none_or(|last| now().saturating_sub(last) >= interval * 60);
+            if due {
+                if let Err(e) = sync(&app, &subscription.id) {
+                    println!("Failed to sync subscription {}: {}", subscription.id, e);
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn list_subscriptions(app: AppHandle) -> Result<Vec<Subscription>, AppError> {
+    load(&app)
+}
+
+// Subscribe to a channel or playlist
+#[tauri::command]
+pub async fn add_subscription(
+    app: AppHandle,
+    url: String,
+    format: Option<String>,
+    output_dir: Option<String>,
+    filters: Option<SubscriptionFilters>,
+    interval_minutes: Option<u64>,
+) -> Result<Subscription, AppError> {
+    let classification = urls::classify(&url)?;
+    if matches!(classification.kind, UrlKind::Video | UrlKind::Short | UrlKind::Story) {
+        return Err(AppError::invalid("Subscriptions need a channel or playlist URL"));
+    }
+    let filters = filters.unwrap_or_default();
+    filters.validate()?;
+
+    use uuid::Uuid;
+    let subscription = Subscription {
+        id: Uuid::new_v4().to_string(),
+        url: listing_url(&classification.url),
+        title: None,
+        format,
+        output_dir,
+        filters,
+        interval_minutes,
+        enabled: true,
+        created_at: now(),
+        last_synced_at: None,
+        last_attempt_at: None,
+        last_error: None,
+        seen: Vec::new(),
+    };
+    modify(&app, |subscriptions| {
+        subscriptions.push(subscription.clone());
+        Ok(())
+    })?;
+    Ok(subscription)
+}
+
+// Replace a subscription's settings, sync state is kept
+#[tauri::command]
+pub async fn update_subscription(app: AppHandle, subscription: Subscription) -> Result<Subscription, AppError> {
+    subscription.filters.validate()?;
+    modify(&app, |subscriptions| {
+        let existing = find_mut(subscriptions, &subscription.id)?;
+        existing.format = subscription.format;
+        existing.output_dir = subscription.output_dir;
+        // New filters may accept what the old ones turned down
+        existing.filters = subscription.filters;
+        existing.seen.clear();
+        existing.interval_minutes = subscription.interval_minutes;
+        existing.enabled = subscription.enabled;
+        Ok(existing.clone())
+    })
+}
+
+// Stop following a subscription, downloaded files are left alone
+#[tauri::command]
+pub async fn remove_subscription(app: AppHandle, id: String) -> Result<(), AppError> {
+    modify(&app, |subscriptions| {
+        let before = subscriptions.len();
+        subscriptions.retain(|subscription| subscription.id != id);
+        if subscriptions.len() == before {
+            return Err(AppError::not_found(format!("Subscription not found: {}", id)));
+        }
+        Ok(())
+    })
+}
+
+// Sync one subscription now
+#[tauri::command]
+pub async fn sync_subscription(app: AppHandle, id: String) -> Result<SyncResult, AppError> {
+    sync(&app, &id)
+}
+
+// Sync every enabled subscription now, failures are reported per
+// subscription through its last_error
+#[tauri::command]
+pub async fn sync_all_subscriptions(app: AppHandle) -> Result<Vec<SyncResult>, AppError> {
+    let mut results = Vec::new();
+    for subscription in load(&app)? {
+        if !subscription.enabled {
+            continue;
+        }
+        match sync(&app, &subscription.id) {
+            Ok(result) => results.push(result),
+            Err(e) => println!("Failed to sync subscription {}: {}", subscription.id, e),
+        }
+    }
+    Ok(results)
+}
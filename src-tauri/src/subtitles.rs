@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    #[default]
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleMode {
+    // Separate files next to the media
+    #[default]
+    Sidecar,
+    // Soft subtitle tracks muxed into MP4/MKV/WebM by ffmpeg
+    Embed,
+}
+
+// Subtitles to fetch along with a download
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SubtitleOptions {
+    // Language codes, the subtitle_language setting when empty. yt-dlp
+    // also takes "all" and patterns like "en.*".
+    pub languages: Vec<String>,
+    // Uploaded subtitles
+    pub manual: bool,
+    // Automatically generated captions
+    pub auto: bool,
+    pub format: SubtitleFormat,
+    pub mode: SubtitleMode,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        SubtitleOptions {
+            languages: Vec::new(),
+            manual: true,
+            auto: false,
+            format: SubtitleFormat::default(),
+            mode: SubtitleMode::default(),
+        }
+    }
+}
+
+fn is_valid_language(language: &str) -> bool {
+    !language.is_empty()
+        && language.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '*'))
+}
+
+impl SubtitleOptions {
+    // yt-dlp arguments, default_language is used when no language was chosen
+    pub fn args(&self, default_language: &str) -> Result<Vec<String>, AppError> {
+        if !self.manual && !self.auto {
+            return Err(AppError::invalid("Choose uploaded subtitles, automatic captions or both"));
+        }
+        let languages = if self.languages.is_empty() {
+            vec![default_language.to_string()]
+        } else {
+            self.languages.iter().map(|language| language.trim().to_string()).collect()
+        };
+        if let Some(language) = languages.iter().find(|language| !is_valid_language(language)) {
+            return Err(AppError::invalid(format!("Invalid subtitle language: {}", language)));
+        }
+
+        let mut args = Vec::new();
+        if self.manual {
+            args.push("--write-subs".to_string());
+        }
+        if self.auto {
+            args.push("--write-auto-subs".to_string());
+        }
+        args.push("--sub-langs".to_string());
+        args.push(languages.join(","));
+        args.push("--convert-subs".to_string());
+        args.push(self.format.as_str().to_string());
+        if self.mode == SubtitleMode::Embed {
+            // Once muxed in, the separate files are removed by yt-dlp
+            args.push("--embed-subs".to_string());
+        }
+        Ok(args)
+    }
+}
@@ -0,0 +1,322 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::whisper::Transcription;
+
+// A word with its timing, in seconds
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Word {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    // Mean probability of the word's whisper tokens
+    pub probability: f32,
+}
+
+// One timed piece of a transcript, times in seconds
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    // Mean whisper token probability, None for captions
+    pub confidence: Option<f32>,
+    // Only filled when word timestamps were asked for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<Word>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    Txt,
+    Srt,
+    Vtt,
+    // WebVTT with karaoke style timestamp tags before every word
+    #[serde(rename = "vtt_words")]
+    VttWords,
+    Json,
+    Tsv,
+}
+
+// Transcripts are kept so they can be exported later
+fn transcripts_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?
+        .join("transcripts");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn transcript_path(app: &AppHandle, id: &str) -> Result<PathBuf, AppError> {
+    // Ids are uuids, anything else could point outside the folder
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(AppError::invalid(format!("Invalid transcript id: {}", id)));
+    }
+    Ok(transcripts_dir(app)?.join(format!("{}.json", id)))
+}
+
+pub fn save(app: &AppHandle, transcription: &Transcription) -> Result<(), AppError> {
+    let path = transcript_path(app, &transcription.id)?;
+    let json = serde_json::to_string(transcription)
+        .map_err(|e| format!("Failed to serialize transcript: {}", e))?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, json)?;
+    fs::rename(&temp, &path)?;
+    Ok(())
+}
+
+pub fn load(app: &AppHandle, id: &str) -> Result<Transcription, AppError> {
+    let path = transcript_path(app, id)?;
+    let json = fs::read_to_string(&path)
+        .map_err(|_| AppError::not_found(format!("Transcript not found: {}", id)))?;
+    serde_json::from_str(&json)
+        .map_err(|e| AppError::from(format!("Failed to read transcript: {}", e)))
+}
+
+// "01:02:03,456" for SRT, "01:02:03.456" for WebVTT
+fn timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+pub fn to_srt(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    for (i, segment) in segments.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            timestamp(segment.start, ','),
+            timestamp(segment.end, ','),
+            segment.text.trim()
+        ));
+    }
+    out
+}
+
+pub fn to_vtt(segments: &[Segment]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for segment in segments {
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            timestamp(segment.start, '.'),
+            timestamp(segment.end, '.'),
+            segment.text.trim()
+        ));
+    }
+    out
+}
+
+// Cue text with a timestamp tag at each word, players highlight the
+// words as they are reached. Segments without words stay plain.
+fn highlighted_text(segment: &Segment) -> String {
+    let Some((first, rest)) = segment.words.split_first() else {
+        return segment.text.trim().to_string();
+    };
+    let mut text = first.text.trim().to_string();
+    for word in rest {
+        text.push_str(&format!("<{}><c> {}</c>", timestamp(word.start, '.'), word.text.trim()));
+    }
+    text
+}
+
+pub fn to_vtt_words(segments: &[Segment]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for segment in segments {
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            timestamp(segment.start, '.'),
+            timestamp(segment.end, '.'),
+            highlighted_text(segment)
+        ));
+    }
+    out
+}
+
+// Same layout as whisper.cpp's -otsv, times in milliseconds
+pub fn to_tsv(segments: &[Segment]) -> String {
+    let mut out = String::from("start\tend\ttext\n");
+    for segment in segments {
+        out.push_str(&format!(
+            "{}\t{}\t{}\n",
+            (segment.start * 1000.0).round() as u64,
+            (segment.end * 1000.0).round() as u64,
+            segment.text.trim().replace(['\t', '\n'], " ")
+        ));
+    }
+    out
+}
+
+pub fn render(transcription: &Transcription, format: TranscriptFormat) -> Result<String, AppError> {
+    Ok(match format {
+        TranscriptFormat::Txt => format!("{}\n", transcription.text),
+        TranscriptFormat::Srt => to_srt(&transcription.segments),
+        TranscriptFormat::Vtt => to_vtt(&transcription.segments),
+        TranscriptFormat::VttWords => to_vtt_words(&transcription.segments),
+        TranscriptFormat::Tsv => to_tsv(&transcription.segments),
+        TranscriptFormat::Json => serde_json::to_string_pretty(transcription)
+            .map_err(|e| format!("Failed to serialize transcript: {}", e))?,
+    })
+}
+
+// "00:01:02.500" or "01:02.500" in seconds, SRT style commas are
+// accepted too
+fn parse_vtt_timestamp(text: &str) -> Option<f64> {
+    let mut seconds = 0.0;
+    for part in text.trim().split(':') {
+        seconds = seconds * 60.0 + part.replace(',', ".").parse::<f64>().ok()?;
+    }
+    Some(seconds)
+}
+
+// Cues of a WebVTT caption file. Auto captions repeat the previous line
+// at the top of every cue, those repeats are dropped.
+pub fn parse_vtt(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut last_text = String::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let Some((start, rest)) = line.split_once("-->") else {
+            continue;
+        };
+        let end = rest.split_whitespace().next().unwrap_or_default();
+        let (Some(start), Some(end)) = (parse_vtt_timestamp(start), parse_vtt_timestamp(end)) else {
+            continue;
+        };
+
+        let mut text = Vec::new();
+        for cue_line in lines.by_ref() {
+            // Only an empty line ends the cue, auto captions start theirs
+            // with a line holding a single space
+            if cue_line.is_empty() {
+                break;
+            }
+            // Drop inline timestamp and styling tags
+            let mut clean = String::new();
+            let mut in_tag = false;
+            for c in cue_line.chars() {
+                match c {
+                    '<' => in_tag = true,
+                    '>' => in_tag = false,
+                    _ if !in_tag => clean.push(c),
+                    _ => {}
+                }
+            }
+            let clean = clean.trim().to_string();
+            if !clean.is_empty() && clean != last_text {
+                last_text = clean.clone();
+                text.push(clean);
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment {
+                start,
+                end,
+                text: text.join(" "),
+                confidence: None,
+                words: Vec::new(),
+            });
+        }
+    }
+    segments
+}
+
+// Write a transcript to a file as TXT, SRT, WebVTT, word-highlighted
+// WebVTT, JSON or TSV
+#[tauri::command]
+pub async fn export_transcript(
+    app: AppHandle,
+    id: String,
+    format: TranscriptFormat,
+    path: String,
+) -> Result<String, AppError> {
+    println!("Exporting transcript {} as {:?} to {}", id, format, path);
+    let transcription = load(&app, &id)?;
+    fs::write(&path, render(&transcription, format)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAPTIONS: &str = include_str!("../tests/fixtures/auto-captions.vtt");
+    const CAPTIONS_SRT: &str = include_str!("../tests/fixtures/auto-captions.srt");
+
+    fn segment(start: f64, end: f64, text: &str) -> Segment {
+        Segment {
+            start,
+            end,
+            text: text.to_string(),
+            confidence: None,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(timestamp(3723.456, ','), "01:02:03,456");
+        assert_eq!(timestamp(3723.456, '.'), "01:02:03.456");
+        // Hours aren't capped at two digits
+        assert_eq!(timestamp(360_000.0, ','), "100:00:00,000");
+        // Rounding carries into seconds, minutes and hours
+        assert_eq!(timestamp(0.9995, '.'), "00:00:01.000");
+        assert_eq!(timestamp(59.9995, '.'), "00:01:00.000");
+        assert_eq!(timestamp(3599.9995, ','), "01:00:00,000");
+        assert_eq!(timestamp(-1.0, '.'), "00:00:00.000");
+    }
+
+    #[test]
+    fn serializes_segments() {
+        let segments = [segment(0.0, 1.5, " first "), segment(1.5, 3.0, "second\tline")];
+        assert_eq!(
+            to_srt(&segments),
+            "1\n00:00:00,000 --> 00:00:01,500\nfirst\n\n2\n00:00:01,500 --> 00:00:03,000\nsecond\tline\n\n"
+        );
+        assert_eq!(
+            to_vtt(&segments),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nfirst\n\n00:00:01.500 --> 00:00:03.000\nsecond\tline\n\n"
+        );
+        assert_eq!(to_tsv(&segments), "start\tend\ttext\n0\t1500\tfirst\n1500\t3000\tsecond line\n");
+    }
+
+    #[test]
+    fn highlights_words() {
+        let mut with_words = segment(1.0, 2.0, "hello world");
+        with_words.words = vec![
+            Word { start: 1.0, end: 1.4, text: "hello".to_string(), probability: 0.9 },
+            Word { start: 1.5, end: 2.0, text: " world".to_string(), probability: 0.8 },
+        ];
+        assert_eq!(
+            to_vtt_words(&[with_words, segment(2.0, 3.0, "plain")]),
+            "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nhello<00:00:01.500><c> world</c>\n\n\
+             00:00:02.000 --> 00:00:03.000\nplain\n\n"
+        );
+    }
+
+    #[test]
+    fn parses_vtt_captions() {
+        let segments = parse_vtt(CAPTIONS);
+        let texts: Vec<&str> = segments.iter().map(|segment| segment.text.as_str()).collect();
+        // Inline tags are stripped and the repeated caption lines dropped
+        assert_eq!(texts, [
+            "hello there everyone",
+            "welcome back",
+            "short form timestamps",
+            "comma timestamps from converted SRT",
+        ]);
+        assert_eq!(segments[2].start, 65.25);
+        assert_eq!(segments[3].end, 72.5);
+        assert_eq!(to_srt(&segments), CAPTIONS_SRT);
+    }
+}
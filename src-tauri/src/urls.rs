@@ -0,0 +1,350 @@
+use std::process::Command;
+use serde::Serialize;
+use url::Url;
+
+use crate::error::{classify_ytdlp_error, AppError};
+use crate::get_ytdlp_path;
+
+// Query parameters that only track where a link was shared from
+const TRACKING_PARAMS: &[&str] = &[
+    "si", "feature", "pp", "fbclid", "gclid", "igshid", "igsh", "ref", "ref_src",
+    "ref_url", "is_from_webapp", "sender_device", "share_id", "mibextid", "rdid",
+];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlKind {
+    Video,
+    Playlist,
+    Channel,
+    Live,
+    Short,
+    Story,
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UrlClassification {
+    // URL with tracking parameters removed and short links expanded
+    pub url: String,
+    // e.g. "youtube", "soundcloud", "vimeo", None for unknown sites
+    pub platform: Option<String>,
+    pub kind: UrlKind,
+    // Video, playlist or channel id depending on kind
+    pub id: Option<String>,
+    // Playlist a single video was opened from, e.g. watch?v=..&list=..
+    pub playlist_id: Option<String>,
+    // True when yt-dlp was asked because the URL alone was inconclusive
+    pub confirmed: bool,
+}
+
+impl UrlClassification {
+    // Whether the URL should be offered as a list of videos
+    pub fn is_collection(&self) -> bool {
+        matches!(self.kind, UrlKind::Playlist | UrlKind::Channel) || self.playlist_id.is_some()
+    }
+}
+
+fn is_tracking_param(name: &str) -> bool {
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name)
+}
+
+fn strip_tracking(url: &mut Url) {
+    let kept: Vec<(String, String)> = url.query_pairs()
+        .filter(|(name, _)| !is_tracking_param(name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+}
+
+fn query_param(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+        .filter(|value| !value.is_empty())
+}
+
+fn host_is(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+fn segment(segments: &[&str], index: usize) -> Option<String> {
+    segments.get(index)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.to_string())
+}
+
+type Parsed = (&'static str, UrlKind, Option<String>);
+
+fn classify_youtube(url: &mut Url, segments: &[&str]) -> Parsed {
+    let playlist = query_param(url, "list");
+    let host = url.host_str().unwrap_or("").to_string();
+
+    // youtu.be/<id> is a short link for watch?v=<id>
+    if host_is(&host, "youtu.be") {
+        let id = segment(segments, 0);
+        if let Some(id) = &id {
+            let mut expanded = Url::parse("https://www.youtube.com/watch").unwrap();
+            expanded.query_pairs_mut().append_pair("v", id);
+            for (name, value) in url.query_pairs() {
+                expanded.query_pairs_mut().append_pair(&name, &value);
+            }
+            *url = expanded;
+        }
+        return ("youtube", UrlKind::Video, id);
+    }
+
+    let first = segments.first().copied().unwrap_or("");
+    match first {
+        "watch" => match query_param(url, "v") {
+            Some(id) => ("youtube", UrlKind::Video, Some(id)),
+            None if playlist.is_some() => ("youtube", UrlKind::Playlist, playlist),
+            None => ("youtube", UrlKind::Unknown, None),
+        },
+        "playlist" => ("youtube", UrlKind::Playlist, playlist),
+        "shorts" => ("youtube", UrlKind::Short, segment(segments, 1)),
+        "live" => ("youtube", UrlKind::Live, segment(segments, 1)),
+        "embed" | "v" => ("youtube", UrlKind::Video, segment(segments, 1)),
+        "channel" | "c" | "user" => ("youtube", UrlKind::Channel, segment(segments, 1)),
+        handle if handle.starts_with('@') => {
+            // /@handle/live is the channel's current stream
+            if segments.get(1) == Some(&"live") {
+                ("youtube", UrlKind::Live, Some(handle.to_string()))
+            } else {
+                ("youtube", UrlKind::Channel, Some(handle.to_string()))
+            }
+        }
+        _ => ("youtube", UrlKind::Unknown, None),
+    }
+}
+
+fn classify_soundcloud(segments: &[&str]) -> Parsed {
+    match segments {
+        [] | [""] => ("soundcloud", UrlKind::Unknown, None),
+        [user] => ("soundcloud", UrlKind::Channel, Some(user.to_string())),
+        [user, "sets", set, ..] => ("soundcloud", UrlKind::Playlist, Some(format!("{}/sets/{}", user, set))),
+        [user, "tracks" | "albums" | "reposts" | "likes" | "popular-tracks"] => {
+            ("soundcloud", UrlKind::Channel, Some(user.to_string()))
+        }
+        [user, track, ..] => ("soundcloud", UrlKind::Video, Some(format!("{}/{}", user, track))),
+    }
+}
+
+fn classify_vimeo(host: &str, segments: &[&str]) -> Parsed {
+    let numeric = |segment: &&str| !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit());
+    if host_is(host, "player.vimeo.com") {
+        return ("vimeo", UrlKind::Video, segment(segments, 1));
+    }
+    match segments {
+        [id, ..] if numeric(id) => ("vimeo", UrlKind::Video, Some(id.to_string())),
+        ["showcase" | "album", id, ..] => ("vimeo", UrlKind::Playlist, Some(id.to_string())),
+        ["channels", _, id, ..] if numeric(id) => ("vimeo", UrlKind::Video, Some(id.to_string())),
+        ["channels", name, ..] => ("vimeo", UrlKind::Channel, Some(name.to_string())),
+        ["event", id, ..] => ("vimeo", UrlKind::Live, Some(id.to_string())),
+        [name] if !name.is_empty() => ("vimeo", UrlKind::Channel, Some(name.to_string())),
+        _ => ("vimeo", UrlKind::Unknown, None),
+    }
+}
+
+fn classify_instagram(segments: &[&str]) -> Parsed {
+    match segments {
+        ["p" | "tv", id, ..] => ("instagram", UrlKind::Video, Some(id.to_string())),
+        ["reel" | "reels", id, ..] => ("instagram", UrlKind::Short, Some(id.to_string())),
+        ["stories", _, id, ..] => ("instagram", UrlKind::Story, Some(id.to_string())),
+        ["stories", user] => ("instagram", UrlKind::Story, Some(user.to_string())),
+        [user] if !user.is_empty() => ("instagram", UrlKind::Channel, Some(user.to_string())),
+        _ => ("instagram", UrlKind::Unknown, None),
+    }
+}
+
+fn classify_tiktok(segments: &[&str]) -> Parsed {
+    match segments {
+        [user, "video" | "photo", id, ..] if user.starts_with('@') => {
+            ("tiktok", UrlKind::Short, Some(id.to_string()))
+        }
+        [user, "live"] if user.starts_with('@') => ("tiktok", UrlKind::Live, Some(user.to_string())),
+        [user] if user.starts_with('@') => ("tiktok", UrlKind::Channel, Some(user.to_string())),
+        _ => ("tiktok", UrlKind::Unknown, None),
+    }
+}
+
+fn classify_twitter(segments: &[&str]) -> Parsed {
+    match segments {
+        [_, "status", id, ..] => ("twitter", UrlKind::Video, Some(id.to_string())),
+        ["i", "broadcasts" | "spaces", id, ..] => ("twitter", UrlKind::Live, Some(id.to_string())),
+        [user] if !user.is_empty() => ("twitter", UrlKind::Channel, Some(user.to_string())),
+        _ => ("twitter", UrlKind::Unknown, None),
+    }
+}
+
+fn classify_facebook(url: &Url, segments: &[&str]) -> Parsed {
+    match segments {
+        ["reel", id, ..] => ("facebook", UrlKind::Short, Some(id.to_string())),
+        ["watch", ..] => ("facebook", UrlKind::Video, query_param(url, "v")),
+        [_, "videos", id, ..] | ["videos", id, ..] => ("facebook", UrlKind::Video, Some(id.to_string())),
+        ["stories", ..] => ("facebook", UrlKind::Story, None),
+        _ => ("facebook", UrlKind::Unknown, None),
+    }
+}
+
+fn classify_twitch(segments: &[&str]) -> Parsed {
+    match segments {
+        ["videos", id, ..] => ("twitch", UrlKind::Video, Some(id.to_string())),
+        [_, "clip", id, ..] => ("twitch", UrlKind::Short, Some(id.to_string())),
+        [channel] if !channel.is_empty() => ("twitch", UrlKind::Live, Some(channel.to_string())),
+        _ => ("twitch", UrlKind::Unknown, None),
+    }
+}
+
+// Classify a URL from its structure alone
+pub fn classify(input: &str) -> Result<UrlClassification, AppError> {
+    let mut url = Url::parse(input.trim())
+        .map_err(|e| AppError::invalid(format!("Invalid URL {}: {}", input, e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::invalid(format!("Unsupported URL scheme: {}", url.scheme())));
+    }
+    strip_tracking(&mut url);
+
+    let host = url.host_str().unwrap_or("").to_lowercase();
+    let path = url.path().to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let parsed = if host_is(&host, "youtube.com") || host_is(&host, "youtu.be") || host_is(&host, "youtube-nocookie.com") {
+        Some(classify_youtube(&mut url, &segments))
+    } else if host_is(&host, "soundcloud.com") {
+        Some(classify_soundcloud(&segments))
+    } else if host_is(&host, "vimeo.com") {
+        Some(classify_vimeo(&host, &segments))
+    } else if host_is(&host, "instagram.com") {
+        Some(classify_instagram(&segments))
+    } else if host_is(&host, "tiktok.com") {
+        Some(classify_tiktok(&segments))
+    } else if host_is(&host, "twitter.com") || host_is(&host, "x.com") {
+        Some(classify_twitter(&segments))
+    } else if host_is(&host, "facebook.com") || host_is(&host, "fb.watch") {
+        Some(classify_facebook(&url, &segments))
+    } else if host_is(&host, "twitch.tv") {
+        Some(classify_twitch(&segments))
+    } else {
+        None
+    };
+
+    let (platform, kind, id) = match parsed {
+        Some((platform, kind, id)) => (Some(platform.to_string()), kind, id),
+        None => (None, UrlKind::Unknown, None),
+    };
+    let playlist_id = if platform.as_deref() == Some("youtube") && kind != UrlKind::Playlist {
+        query_param(&url, "list")
+    } else {
+        None
+    };
+
+    Ok(UrlClassification {
+        url: url.to_string(),
+        platform,
+        kind,
+        id,
+        playlist_id,
+        confirmed: false,
+    })
+}
+
+// Ask yt-dlp what the URL points to without resolving every entry
+fn confirm_with_ytdlp(classification: &mut UrlClassification) -> Result<(), AppError> {
+    let output = Command::new(get_ytdlp_path())
+        .args([
+            "--flat-playlist",
+            "--playlist-end", "1",
+            "--no-warnings",
+            "-J",
+            &classification.url,
+        ])
+        .output()
+        .map_err(|e| AppError::spawn("yt-dlp", e))?;
+
+    if !output.status.success() {
+        return Err(classify_ytdlp_error(&String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let data: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    classification.kind = if data["_type"].as_str() == Some("playlist") {
+        UrlKind::Playlist
+    } else if data["is_live"].as_bool() == Some(true) {
+        UrlKind::Live
+    } else {
+        UrlKind::Video
+    };
+    if let Some(id) = data["id"].as_str() {
+        classification.id = Some(id.to_string());
+    }
+    if classification.platform.is_none() {
+        classification.platform = data["extractor_key"].as_str()
+            .or_else(|| data["ie_key"].as_str())
+            .map(|key| key.to_lowercase());
+    }
+    classification.confirmed = true;
+    Ok(())
+}
+
+// Work out platform, content kind and id for a URL. With confirm set,
+// URLs the patterns can't place are checked with yt-dlp.
+#[tauri::command]
+pub async fn classify_url(url: String, confirm: Option<bool>) -> Result<UrlClassification, AppError> {
+    let mut classification = classify(&url)?;
+    if classification.kind == UrlKind::Unknown && confirm.unwrap_or(false) {
+        confirm_with_ytdlp(&mut classification)?;
+    }
+    Ok(classification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_url_shapes() {
+        let cases = [
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ", Some("youtube"), UrlKind::Video, Some("dQw4w9WgXcQ"), false),
+            ("https://www.youtube.com/shorts/abc123", Some("youtube"), UrlKind::Short, Some("abc123"), false),
+            ("https://youtu.be/dQw4w9WgXcQ", Some("youtube"), UrlKind::Video, Some("dQw4w9WgXcQ"), false),
+            ("https://youtu.be/dQw4w9WgXcQ?list=PL123", Some("youtube"), UrlKind::Video, Some("dQw4w9WgXcQ"), true),
+            ("https://www.youtube.com/playlist?list=PL123", Some("youtube"), UrlKind::Playlist, Some("PL123"), true),
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123", Some("youtube"), UrlKind::Video, Some("dQw4w9WgXcQ"), true),
+            ("https://www.youtube.com/watch?list=PL123", Some("youtube"), UrlKind::Playlist, Some("PL123"), true),
+            ("https://music.youtube.com/watch?v=abc&list=RDAMVMabc", Some("youtube"), UrlKind::Video, Some("abc"), true),
+            ("https://music.youtube.com/playlist?list=OLAK5uy", Some("youtube"), UrlKind::Playlist, Some("OLAK5uy"), true),
+            ("https://www.youtube.com/@channel", Some("youtube"), UrlKind::Channel, Some("@channel"), true),
+            ("https://www.youtube.com/@channel/live", Some("youtube"), UrlKind::Live, Some("@channel"), false),
+            ("https://soundcloud.com/artist/sets/album", Some("soundcloud"), UrlKind::Playlist, Some("artist/sets/album"), true),
+            ("https://vimeo.com/showcase/12345", Some("vimeo"), UrlKind::Playlist, Some("12345"), true),
+            ("https://example.com/video.mp4", None, UrlKind::Unknown, None, false),
+        ];
+        for (url, platform, kind, id, collection) in cases {
+            let classification = classify(url).unwrap();
+            assert_eq!(classification.platform.as_deref(), platform, "{}", url);
+            assert_eq!(classification.kind, kind, "{}", url);
+            assert_eq!(classification.id.as_deref(), id, "{}", url);
+            assert_eq!(classification.is_collection(), collection, "{}", url);
+        }
+    }
+
+    #[test]
+    fn expands_short_links_and_strips_tracking() {
+        let classification = classify("https://youtu.be/dQw4w9WgXcQ?si=abc&t=42").unwrap();
+        assert_eq!(classification.url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42");
+
+        let classification = classify("https://www.youtube.com/watch?v=abc&utm_source=x&feature=share").unwrap();
+        assert_eq!(classification.url, "https://www.youtube.com/watch?v=abc");
+    }
+
+    #[test]
+    fn rejects_unparsable_input() {
+        assert!(classify("not a url").is_err());
+        assert!(classify("ftp://example.com/file").is_err());
+    }
+}
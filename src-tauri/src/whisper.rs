@@ -0,0 +1,221 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, ErrorCode};
+use crate::transcript::{Segment, Word};
+
+// Language value that lets whisper detect the spoken language
+pub const AUTO_LANGUAGE: &str = "auto";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Transcription {
+    // For export_transcript
+    pub id: String,
+    pub text: String,
+    pub segments: Vec<Segment>,
+    // Language the transcript is in, detected when "auto" was asked for
+    pub language: Option<String>,
+    // Whisper's confidence in the detected language, 0 to 1
+    pub language_probability: Option<f32>,
+}
+
+// Folders the whisper binary and models ship in
+pub fn resource_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if cfg!(target_os = "macos") {
+        if let Ok(exe_path) = std::env::current_exe() {
+            // For bundled macOS app: executable is at Contents/MacOS/Grably
+            // Resources are at Contents/Resources/resources/
+            if let Some(contents_dir) = exe_path.parent().and_then(|dir| dir.parent()) {
+                dirs.push(contents_dir.join("Resources").join("resources"));
+            }
+            // For development
+            if let Some(parent) = exe_path.parent() {
+                dirs.push(parent.join("resources"));
+            }
+        }
+    }
+    dirs.push(Path::new(env!("CARGO_MANIFEST_DIR")).join("resources"));
+    dirs
+}
+
+// The bundled whisper.cpp binary
+pub fn whisper_binary() -> Result<PathBuf, AppError> {
+    resource_dirs().into_iter()
+        .map(|dir| dir.join("whisper"))
+        .find(|path| path.exists())
+        .inspect(|path| println!("Found whisper at: {:?}", path))
+        .ok_or_else(|| AppError::new(ErrorCode::ModelMissing, "whisper.cpp not found in resources"))
+}
+
+// whisper.cpp language codes are two or three lowercase letters
+pub fn validate_language(language: &str) -> Result<(), AppError> {
+    let valid = language == AUTO_LANGUAGE
+        || ((2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase()));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::invalid(format!("Unknown whisper language: {}", language)))
+    }
+}
+
+// "whisper_full_with_state: auto-detected language: de (p = 0.953125)"
+fn parse_detected_language(stderr: &str) -> Option<(String, f32)> {
+    let line = stderr.lines().find(|line| line.contains("auto-detected language:"))?;
+    let (_, rest) = line.split_once("auto-detected language:")?;
+    let (language, probability) = rest.split_once("(p =")?;
+    let probability = probability.trim().trim_end_matches(')').trim().parse().ok()?;
+    Some((language.trim().to_string(), probability))
+}
+
+impl Transcription {
+    pub fn new(segments: Vec<Segment>, language: Option<String>, language_probability: Option<f32>) -> Self {
+        let text = segments.iter()
+            .map(|segment| segment.text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Transcription {
+            id: uuid::Uuid::new_v4().to_string(),
+            text,
+            segments,
+            language,
+            language_probability,
+        }
+    }
+}
+
+// Join a segment's tokens into words. Tokens starting with a space begin
+// a new word, the rest (word pieces, punctuation) extend the current one.
+fn parse_words(tokens: &[serde_json::Value]) -> Vec<Word> {
+    // Words with the probabilities of their tokens
+    let mut words: Vec<(Word, Vec<f32>)> = Vec::new();
+    for token in tokens {
+        let text = token["text"].as_str().unwrap_or_default();
+        if text.is_empty() || text.starts_with("[_") {
+            continue;
+        }
+        let (Some(start), Some(end)) = (token["offsets"]["from"].as_f64(), token["offsets"]["to"].as_f64()) else {
+            continue;
+        };
+        let probability = token["p"].as_f64().unwrap_or_default() as f32;
+        match words.last_mut() {
+            Some((word, probabilities)) if !text.starts_with(' ') => {
+                word.text.push_str(text);
+                word.end = end / 1000.0;
+                probabilities.push(probability);
+            }
+            _ => words.push((
+                Word {
+                    start: start / 1000.0,
+                    end: end / 1000.0,
+                    text: text.trim_start().to_string(),
+                    probability,
+                },
+                vec![probability],
+            )),
+        }
+    }
+    words.into_iter()
+        .map(|(mut word, probabilities)| {
+            word.probability = probabilities.iter().sum::<f32>() / probabilities.len() as f32;
+            word
+        })
+        .collect()
+}
+
+// Segments of whisper.cpp's full JSON output (-ojf)
+fn parse_segments(json: &serde_json::Value, word_timestamps: bool) -> Vec<Segment> {
+    let Some(entries) = json["transcription"].as_array() else {
+        return Vec::new();
+    };
+    entries.iter()
+        .filter_map(|entry| {
+            let probabilities: Vec<f32> = entry["tokens"].as_array()
+                .map(|tokens| {
+                    tokens.iter()
+                        // Special tokens like [_BEG_] and [_TT_150] aren't speech
+                        .filter(|token| !token["text"].as_str().unwrap_or_default().starts_with("[_"))
+                        .filter_map(|token| token["p"].as_f64())
+                        .map(|p| p as f32)
+                        .collect()
+                })
+                .unwrap_or_default();
+            let confidence = (!probabilities.is_empty())
+                .then(|| probabilities.iter().sum::<f32>() / probabilities.len() as f32);
+            let words = match entry["tokens"].as_array() {
+                Some(tokens) if word_timestamps => parse_words(tokens),
+                _ => Vec::new(),
+            };
+            Some(Segment {
+                start: entry["offsets"]["from"].as_f64()? / 1000.0,
+                end: entry["offsets"]["to"].as_f64()? / 1000.0,
+                text: entry["text"].as_str()?.trim().to_string(),
+                confidence,
+                words,
+            })
+        })
+        .collect()
+}
+
+// Transcribe an audio file whisper.cpp can read with the given model.
+// The full JSON output always carries token timings, word_timestamps
+// decides whether they are turned into words.
+pub fn run(
+    audio_path: &str,
+    language: &str,
+    model_path: &Path,
+    word_timestamps: bool,
+) -> Result<Transcription, AppError> {
+    validate_language(language)?;
+    let whisper_path = whisper_binary()?;
+    println!("Using whisper model: {:?} (language: {})", model_path, language);
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let output_file = std::env::temp_dir()
+        .join(format!("whisper_output_{}", timestamp))
+        .to_string_lossy()
+        .to_string();
+
+    let output = Command::new(&whisper_path)
+        .args([
+            "-m", model_path.to_str().ok_or("Invalid model path")?,
+            "-f", audio_path,
+            "-oj",
+            "-ojf",
+            "-of", &output_file,
+            "-l", language,
+        ])
+        .output()
+        .map_err(|e| AppError::spawn("whisper.cpp", e))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(AppError::whisper(&stderr));
+    }
+
+    let transcript_path = format!("{}.json", output_file);
+    let json = fs::read_to_string(&transcript_path).map_err(|_| AppError::new(
+        ErrorCode::WhisperFailed,
+        format!("Transcript file not found at {}", transcript_path),
+    ))?;
+    let _ = fs::remove_file(&transcript_path);
+    let json: serde_json::Value = serde_json::from_str(&json)
+        .map_err(|e| AppError::new(ErrorCode::WhisperFailed, format!("Failed to parse whisper output: {}", e)))?;
+
+    let (language, language_probability) = if language == AUTO_LANGUAGE {
+        match parse_detected_language(&stderr) {
+            Some((detected, probability)) => (Some(detected), Some(probability)),
+            None => (json["result"]["language"].as_str().map(|s| s.to_string()), None),
+        }
+    } else {
+        (Some(language.to_string()), None)
+    };
+
+    Ok(Transcription::new(parse_segments(&json, word_timestamps), language, language_probability))
+}
@@ -19,11 +19,22 @@ interface DownloadStatus {
   percent: number;
 }
 
+interface PlaylistItemProgress {
+  id: string;
+  index: number;
+  count: number;
+  title?: string;
+  path?: string;
+  percent: number;
+  aggregate_percent: number;
+}
+
 interface ActiveDownload {
   id: string;
   filename: string;
   progress?: DownloadProgress;
   status?: string;
+  item?: PlaylistItemProgress;
   lastUpdate: number;
 }
 
@@ -47,6 +58,7 @@ export const ActiveDownloads: React.FC = () => {
           id,
           filename,
           status,
+          item: prev.get(id)?.item,
           lastUpdate: Date.now()
         });
         return newMap;
@@ -66,12 +78,26 @@ export const ActiveDownloads: React.FC = () => {
           filename: progress.filename || existing?.filename || 'Download',
           progress,
           status: undefined, // Clear status when we have actual progress
+          item: existing?.item,
           lastUpdate: Date.now()
         });
         return newMap;
       });
     });
 
+    // Listen for playlist item events, the main progress is then the
+    // whole playlist's
+    const unlistenItem = listen<PlaylistItemProgress>('download-item-progress', (event) => {
+      const item = event.payload;
+      setDownloads(prev => {
+        const existing = prev.get(item.id);
+        if (!existing) return prev;
+        const newMap = new Map(prev);
+        newMap.set(item.id, { ...existing, item, lastUpdate: Date.now() });
+        return newMap;
+      });
+    });
+
     // Listen for download complete events to remove from list
     const unlistenComplete = listen<{filename: string, path: string}>('download-complete', (event) => {
       // Remove completed download after a short delay
@@ -109,6 +135,7 @@ export const ActiveDownloads: React.FC = () => {
     return () => {
       unlistenStatus.then(fn => fn());
       unlistenProgress.then(fn => fn());
+      unlistenItem.then(fn => fn());
       unlistenComplete.then(fn => fn());
       clearInterval(cleanupInterval);
     };
@@ -197,6 +224,12 @@ export const ActiveDownloads: React.FC = () => {
                     <p className="text-xs font-bold text-gray-900 truncate" style={{ fontFamily: 'Space Mono, monospace' }}>
                       {download.filename}
                     </p>
+                    {download.item && (
+                      <p className="text-xs text-gray-500 truncate" style={{ fontFamily: 'Space Mono, monospace' }}>
+                        Item {download.item.index} of {download.item.count}
+                        {download.item.title ? `: ${download.item.title}` : ''}
+                      </p>
+                    )}
                     {download.status ? (
                       // Show status message
                       <p className="text-xs text-orange-600 mt-1 animate-pulse" style={{ fontFamily: 'Space Mono, monospace' }}>
@@ -58,18 +58,22 @@ export const UniversalDownloader: React.FC<{ onBack: () => void }> = ({ onBack }
   // Progress is now handled by ActiveDownloads widget
 
   useEffect(() => {
-    // Detect platform from URL
-    if (url) {
-      if (url.includes('instagram.com')) setDetectedPlatform('instagram');
-      else if (url.includes('tiktok.com')) setDetectedPlatform('tiktok');
-      else if (url.includes('twitter.com') || url.includes('x.com')) setDetectedPlatform('twitter');
-      else if (url.includes('facebook.com') || url.includes('fb.')) setDetectedPlatform('facebook');
-      else if (url.includes('reddit.com')) setDetectedPlatform('reddit');
-      else if (url.includes('pinterest.com')) setDetectedPlatform('pinterest');
-      else setDetectedPlatform('');
-    } else {
+    // Detect platform from URL using the backend's site profiles
+    if (!url || !window.__TAURI__) {
       setDetectedPlatform('');
+      return;
     }
+    let cancelled = false;
+    invoke<{ id: string } | null>('detect_site', { url })
+      .then(profile => {
+        if (!cancelled) setDetectedPlatform(profile?.id ?? '');
+      })
+      .catch(() => {
+        if (!cancelled) setDetectedPlatform('');
+      });
+    return () => {
+      cancelled = true;
+    };
   }, [url]);
 
   const downloadContent = async () => {
@@ -87,14 +91,11 @@ export const UniversalDownloader: React.FC<{ onBack: () => void }> = ({ onBack }
       }
       
       // Start download in background (fire and forget)
-      invoke<string>('download_universal', { 
-        url,
-        siteType: detectedPlatform || undefined
-      }).then(() => {
+      invoke<string>('download_universal', { url }).then(() => {
         // Download completed silently
       }).catch(error => {
         console.error('Download failed:', error);
-        toast.error(`Download failed: ${error}`);
+        toast.error(`Download failed: ${error?.message ?? error}`);
       });
       
       // Keep downloading state for 2 seconds to show button feedback
@@ -105,7 +106,7 @@ export const UniversalDownloader: React.FC<{ onBack: () => void }> = ({ onBack }
       }, 2000);
     } catch (error) {
       console.error('Failed to start download:', error);
-      toast.error(`Failed to start download: ${error}`);
+      toast.error(`Failed to start download: ${(error as any)?.message ?? error}`);
       setDownloading(false);
     }
   };
@@ -24,9 +24,29 @@ interface VideoInfo {
   uploader?: string;
   view_count?: number;
   formats: VideoFormat[];
+  chapters: Chapter[];
+  description?: string;
+  upload_date?: string;
+  channel_id?: string;
+  like_count?: number;
+  tags: string[];
+  categories: string[];
+  subtitle_languages: string[];
+  auto_caption_languages: string[];
+  live_status?: string;
+  age_limit?: number;
+  webpage_url?: string;
+}
+
+interface Chapter {
+  index: number;
+  title: string;
+  start_time: number;
+  end_time?: number;
 }
 
 interface PlaylistVideo {
+  index?: number;
   id: string;
   title: string;
   duration?: number;
@@ -40,6 +60,8 @@ interface PlaylistInfo {
   video_count: number;
   videos: PlaylistVideo[];
   thumbnail?: string;
+  offset: number;
+  has_more: boolean;
 }
 
 interface DownloadProgress {
@@ -257,8 +279,11 @@ export const YouTubeDownloader: React.FC<{ onBack: () => void }> = ({ onBack })
     setShowPlayer(false);
     setSelectedVideos(new Set());
     
-    // Check if it's a playlist
-    const playlist = isPlaylistUrl(url);
+    // Check if it's a playlist, the backend also knows channels and
+    // youtu.be links with a list parameter
+    const playlist = window.__TAURI__
+      ? await invoke<boolean>('is_playlist', { url }).catch(() => isPlaylistUrl(url))
+      : isPlaylistUrl(url);
     console.log('URL:', url, 'Is playlist?', playlist);
     setIsPlaylist(playlist);
     